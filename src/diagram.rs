@@ -0,0 +1,207 @@
+//! Renders graphs as Mermaid flowcharts and PlantUML diagrams, which can be embedded in Markdown
+//! docs where dot files aren't rendered. Name scopes are drawn as subgraphs (or packages), so
+//! these work best with a `max_depth` keeping the diagram small.
+use crate::dot::{name_scope, node_style, Scope};
+use crate::graph::{Edge, EdgeKind, Node};
+use petgraph::graph::Graph;
+use petgraph::visit::EdgeRef;
+use std::fmt::Write;
+
+/// The label for a node, the scope is shown by the subgraph it's in so only the last component of
+/// the name is used.
+fn node_label(node: &Node) -> Vec<String> {
+    let name = node
+        .name()
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut label = vec![name, node.ty().to_string()];
+    if let Some(summary) = node.summary() {
+        label.extend(summary.to_string().lines().map(str::to_string));
+    }
+    label
+}
+
+fn edge_label(edge: &Edge) -> Option<String> {
+    match (edge.kind(), edge.output_index()) {
+        (EdgeKind::Call, _) => Some("call".to_string()),
+        (EdgeKind::Control, _) | (_, None) => None,
+        (_, Some(index)) => Some(format!("{}: {}", index, edge)),
+    }
+}
+
+/// Escapes text for a quoted Mermaid label, quotes and angle brackets become entity codes.
+fn escape_mermaid(s: &str) -> String {
+    s.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn write_mermaid_scope(
+    graph: &Graph<Node, Edge>,
+    scope: &Scope,
+    depth: usize,
+    subgraphs: &mut usize,
+    out: &mut String,
+) -> std::fmt::Result {
+    let indent = "    ".repeat(depth);
+    for idx in &scope.nodes {
+        let node = &graph[*idx];
+        let label = node_label(node)
+            .iter()
+            .map(|line| escape_mermaid(line))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        let (open, close) = match node_style(node.ty()).0 {
+            "invhouse" => ("[/", "\\]"),
+            "cylinder" => ("[(", ")]"),
+            "hexagon" => ("{{", "}}"),
+            "box3d" => ("[[", "]]"),
+            _ => ("[", "]"),
+        };
+        writeln!(
+            out,
+            "{}n{}{}\"{}\"{}",
+            indent,
+            idx.index(),
+            open,
+            label,
+            close
+        )?;
+    }
+    for (name, child) in &scope.children {
+        writeln!(
+            out,
+            "{}subgraph s{}[\"{}\"]",
+            indent,
+            subgraphs,
+            escape_mermaid(name)
+        )?;
+        *subgraphs += 1;
+        write_mermaid_scope(graph, child, depth + 1, subgraphs, out)?;
+        writeln!(out, "{}end", indent)?;
+    }
+    Ok(())
+}
+
+fn write_mermaid(graph: &Graph<Node, Edge>, out: &mut String) -> std::fmt::Result {
+    writeln!(out, "flowchart TD")?;
+    let root = Scope::build(graph, name_scope);
+    write_mermaid_scope(graph, &root, 1, &mut 0, out)?;
+    let mut back_edges = vec![];
+    for (i, edge) in graph.edge_references().enumerate() {
+        let arrow = match edge.weight().kind() {
+            EdgeKind::Data => "-->",
+            EdgeKind::Control | EdgeKind::Call => "-.->",
+            EdgeKind::Back => {
+                back_edges.push(i.to_string());
+                "==>"
+            }
+        };
+        let label = edge_label(edge.weight())
+            .map(|label| format!("|\"{}\"|", escape_mermaid(&label)))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "    n{} {}{} n{}",
+            edge.source().index(),
+            arrow,
+            label,
+            edge.target().index()
+        )?;
+    }
+    if !back_edges.is_empty() {
+        writeln!(out, "    linkStyle {} stroke:#e31a1c", back_edges.join(","))?;
+    }
+    for idx in graph.node_indices() {
+        let colour = node_style(graph[idx].ty()).1;
+        if colour != "#ffffff" {
+            writeln!(out, "    style n{} fill:{}", idx.index(), colour)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders the graph as a Mermaid flowchart with each name scope drawn as a subgraph.
+pub fn render_mermaid(graph: &Graph<Node, Edge>) -> String {
+    let mut out = String::new();
+    write_mermaid(graph, &mut out).expect("writing to a string can't fail");
+    out
+}
+
+/// Escapes text for a quoted PlantUML string, which has no way to escape a double quote.
+fn escape_plantuml(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "'")
+}
+
+fn write_plantuml_scope(
+    graph: &Graph<Node, Edge>,
+    scope: &Scope,
+    depth: usize,
+    out: &mut String,
+) -> std::fmt::Result {
+    let indent = "  ".repeat(depth);
+    for idx in &scope.nodes {
+        let node = &graph[*idx];
+        let label = node_label(node)
+            .iter()
+            .map(|line| escape_plantuml(line))
+            .collect::<Vec<_>>()
+            .join("\\n");
+        let (shape, colour) = node_style(node.ty());
+        let element = match shape {
+            "cylinder" => "database",
+            "hexagon" => "hexagon",
+            "box3d" => "node",
+            _ => "rectangle",
+        };
+        writeln!(
+            out,
+            "{}{} \"{}\" as n{} {}",
+            indent,
+            element,
+            label,
+            idx.index(),
+            colour
+        )?;
+    }
+    for (name, child) in &scope.children {
+        writeln!(out, "{}package \"{}\" {{", indent, escape_plantuml(name))?;
+        write_plantuml_scope(graph, child, depth + 1, out)?;
+        writeln!(out, "{}}}", indent)?;
+    }
+    Ok(())
+}
+
+fn write_plantuml(graph: &Graph<Node, Edge>, out: &mut String) -> std::fmt::Result {
+    writeln!(out, "@startuml")?;
+    let root = Scope::build(graph, name_scope);
+    write_plantuml_scope(graph, &root, 0, out)?;
+    for edge in graph.edge_references() {
+        let arrow = match edge.weight().kind() {
+            EdgeKind::Data => "-->",
+            EdgeKind::Control | EdgeKind::Call => "..>",
+            EdgeKind::Back => "-[#e31a1c,bold]->",
+        };
+        let label = edge_label(edge.weight())
+            .map(|label| format!(" : {}", escape_plantuml(&label)))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "n{} {} n{}{}",
+            edge.source().index(),
+            arrow,
+            edge.target().index(),
+            label
+        )?;
+    }
+    writeln!(out, "@enduml")
+}
+
+/// Renders the graph as a PlantUML diagram with each name scope drawn as a package.
+pub fn render_plantuml(graph: &Graph<Node, Edge>) -> String {
+    let mut out = String::new();
+    write_plantuml(graph, &mut out).expect("writing to a string can't fail");
+    out
+}
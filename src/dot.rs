@@ -0,0 +1,225 @@
+use crate::graph::{Edge, EdgeKind, Node};
+use petgraph::dot::{Config, Dot};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Escapes a string so it can be placed inside a quoted DOT string.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Picks a node shape and fill colour based off the op type so the different kinds of op stand
+/// out in the rendered graph.
+pub(crate) fn node_style(ty: &str) -> (&'static str, &'static str) {
+    match ty {
+        "Placeholder" | "PlaceholderWithDefault" | "Input" => ("invhouse", "#a6cee3"),
+        "QUANTIZE" | "DEQUANTIZE" | "FAKE_QUANT" => ("hexagon", "#fb9a99"),
+        "Const" | "Variable" | "VariableV2" | "VarHandleOp" | "ReadVariableOp" | "Initializer" => {
+            ("cylinder", "#d9d9d9")
+        }
+        "Conv2D"
+        | "Conv3D"
+        | "DepthwiseConv2dNative"
+        | "MatMul"
+        | "BatchMatMul"
+        | "BatchMatMulV2"
+        | "Conv"
+        | "Gemm"
+        | "CONV_2D"
+        | "DEPTHWISE_CONV_2D"
+        | "FULLY_CONNECTED" => ("box", "#fdbf6f"),
+        "Relu" | "Relu6" | "Elu" | "Selu" | "LeakyRelu" | "Sigmoid" | "Tanh" | "Softmax" => {
+            ("box", "#b2df8a")
+        }
+        "Block" => ("box3d", "#cab2d6"),
+        _ => ("box", "#ffffff"),
+    }
+}
+
+fn node_attributes(node: &Node) -> String {
+    let (shape, colour) = node_style(node.ty());
+    let mut label = format!("{}\n{}", node.name().to_string_lossy(), node.ty());
+    if let Some(summary) = node.summary() {
+        label = format!("{}\n{}", label, summary);
+    }
+    let mut attributes = format!(
+        "label = \"{}\" shape = {} style = filled fillcolor = \"{}\" ",
+        escape(&label),
+        shape,
+        colour
+    );
+    if !node.attributes().is_empty() {
+        let tooltip = node
+            .attributes()
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, v))
+            .collect::<Vec<_>>()
+            .join("\n");
+        attributes.push_str(&format!("tooltip = \"{}\" ", escape(&tooltip)));
+    }
+    attributes
+}
+
+fn edge_attributes(edge: &Edge) -> String {
+    let label = match edge.output_index() {
+        Some(index) => format!("label = \"{}\\n{}\" ", index, edge),
+        None => String::new(),
+    };
+    match edge.kind() {
+        EdgeKind::Control => "style = dashed ".to_string(),
+        EdgeKind::Call => "label = \"call\" style = dotted ".to_string(),
+        EdgeKind::Data => label,
+        // Back edges don't affect the layout, so loops are drawn top to bottom with the edges
+        // going back up to the start
+        EdgeKind::Back => format!(
+            "{}style = bold color = \"#e31a1c\" constraint = false ",
+            label
+        ),
+    }
+}
+
+/// Renders the graph as a graphviz dot file with readable node and edge labels. Control flow
+/// regions, such as loops and the branches of conditionals, are drawn as clusters.
+pub fn render(graph: &Graph<Node, Edge>) -> String {
+    if graph.node_weights().any(|node| !node.region().is_empty()) {
+        return render_scopes(graph, |_| vec![]);
+    }
+    let dot = Dot::with_attr_getters(
+        graph,
+        &[Config::NodeNoLabel, Config::EdgeNoLabel],
+        &|_, edge| edge_attributes(edge.weight()),
+        &|_, (_, node)| node_attributes(node),
+    );
+    format!("{:?}", dot)
+}
+
+/// A scope in the graph, holding the nodes directly inside it, any nested scopes and the control
+/// flow regions inside it.
+#[derive(Default)]
+pub(crate) struct Scope {
+    pub(crate) nodes: Vec<NodeIndex>,
+    pub(crate) children: BTreeMap<String, Scope>,
+    pub(crate) regions: BTreeMap<String, Scope>,
+}
+
+impl Scope {
+    /// Groups the nodes of the graph into nested scopes, `scope` gives the path of scopes each node
+    /// is inside.
+    pub(crate) fn build(graph: &Graph<Node, Edge>, scope: impl Fn(&Node) -> Vec<String>) -> Self {
+        let mut root = Scope::default();
+        for node in graph.node_indices() {
+            root.insert(scope(&graph[node]), &[], node);
+        }
+        root
+    }
+
+    /// Like `build` with the control flow regions of each node nested inside its innermost scope.
+    fn build_with_regions(graph: &Graph<Node, Edge>, scope: impl Fn(&Node) -> Vec<String>) -> Self {
+        let mut root = Scope::default();
+        for node in graph.node_indices() {
+            root.insert(scope(&graph[node]), graph[node].region(), node);
+        }
+        root
+    }
+
+    fn insert(&mut self, scope: Vec<String>, region: &[String], node: NodeIndex) {
+        let mut current = self;
+        for name in scope {
+            current = current.children.entry(name).or_default();
+        }
+        for label in region {
+            current = current.regions.entry(label.clone()).or_default();
+        }
+        current.nodes.push(node);
+    }
+
+    /// Writes the nodes and clusters in the scope, name scopes are drawn with rounded corners and
+    /// regions dashed. `path` identifies the scope so every cluster gets a unique name.
+    fn write(
+        &self,
+        graph: &Graph<Node, Edge>,
+        path: &Path,
+        depth: usize,
+        out: &mut String,
+    ) -> std::fmt::Result {
+        let indent = "    ".repeat(depth);
+        for node in &self.nodes {
+            writeln!(
+                out,
+                "{}{} [ {}]",
+                indent,
+                node.index(),
+                node_attributes(&graph[*node])
+            )?;
+        }
+        let clusters = self
+            .children
+            .iter()
+            .map(|(name, child)| (name, child, "rounded", path.join(name)))
+            .chain(self.regions.iter().map(|(label, child)| {
+                // Regions are keyed by their label so they can't clash with a name scope
+                let path = path.join(format!("region {}", label));
+                (label, child, "dashed", path)
+            }));
+        for (label, child, style, path) in clusters {
+            writeln!(
+                out,
+                "{}subgraph \"cluster_{}\" {{",
+                indent,
+                escape(&path.to_string_lossy())
+            )?;
+            writeln!(
+                out,
+                "{}    label = \"{}\"; style = {};",
+                indent,
+                escape(label),
+                style
+            )?;
+            child.write(graph, &path, depth + 1, out)?;
+            writeln!(out, "{}}}", indent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the graph with the nodes grouped into nested clusters, `scope` gives the path of
+/// clusters each node is inside and its control flow regions are nested inside those.
+fn render_scopes(graph: &Graph<Node, Edge>, scope: impl Fn(&Node) -> Vec<String>) -> String {
+    let root = Scope::build_with_regions(graph, scope);
+    let mut out = String::from("digraph {\n");
+    root.write(graph, Path::new(""), 1, &mut out)
+        .expect("writing to a string can't fail");
+    for edge in graph.edge_references() {
+        writeln!(
+            out,
+            "    {} -> {} [ {}]",
+            edge.source().index(),
+            edge.target().index(),
+            edge_attributes(edge.weight())
+        )
+        .expect("writing to a string can't fail");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Renders the graph as a graphviz dot file with every name scope drawn as a cluster around the
+/// ops inside it, with control flow regions nested inside. Scopes collapsed by `max_depth` are
+/// drawn as a single block node.
+pub fn render_clusters(graph: &Graph<Node, Edge>) -> String {
+    render_scopes(graph, name_scope)
+}
+
+/// The name scopes a node is inside, outermost first.
+pub(crate) fn name_scope(node: &Node) -> Vec<String> {
+    let scope = node.name().parent().unwrap_or_else(|| Path::new(""));
+    scope
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
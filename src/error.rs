@@ -0,0 +1,100 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong loading a model, with messages saying what to do about it.
+#[derive(Debug)]
+pub enum Error {
+    /// The model couldn't be read
+    Io { path: PathBuf, source: io::Error },
+    /// The model isn't valid for its format
+    Parse { path: PathBuf, message: String },
+    /// The model uses an op type which isn't registered with the TensorFlow runtime
+    UnknownOp { op: String },
+    /// A SavedModel doesn't have a meta graph with the requested tags
+    MetaGraphNotFound {
+        path: PathBuf,
+        tags: Vec<String>,
+        available: Vec<Vec<String>>,
+    },
+    /// The model isn't in any of the formats which can be read
+    UnsupportedFormat { path: PathBuf, reason: String },
+    /// An op name or type isn't valid UTF-8
+    InvalidName(InvalidNameError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => {
+                write!(f, "unable to read {}: {}", path.display(), source)
+            }
+            Error::Parse { path, message } => {
+                write!(f, "{} isn't a valid model: {}", path.display(), message)
+            }
+            Error::UnknownOp { op } => write!(
+                f,
+                "the model uses the op `{}` which isn't registered with TensorFlow, it's likely a \
+                 custom op (such as one from tf.contrib) so its library needs to be loaded",
+                op
+            ),
+            Error::MetaGraphNotFound {
+                path,
+                tags,
+                available,
+            } => {
+                let available = available
+                    .iter()
+                    .map(|tags| format!("[{}]", tags.join(", ")))
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "{} has no meta graph tagged [{}], pick one of {} with --tag",
+                    path.display(),
+                    tags.join(", "),
+                    available.join(" ")
+                )
+            }
+            Error::UnsupportedFormat { path, reason } => write!(
+                f,
+                "{} isn't a supported model format ({}), expected a GraphDef, SavedModel, ONNX \
+                 or TensorFlow Lite model",
+                path.display(),
+                reason
+            ),
+            Error::InvalidName(e) => e.fmt(f),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::InvalidName(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidNameError> for Error {
+    fn from(e: InvalidNameError) -> Self {
+        Error::InvalidName(e)
+    }
+}
+
+/// An op name or type which isn't valid UTF-8.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct InvalidNameError {
+    /// The name with the invalid bytes escaped as `\xNN`
+    pub name: String,
+}
+
+impl fmt::Display for InvalidNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "op name or type isn't valid UTF-8: {}", self.name)
+    }
+}
+
+impl StdError for InvalidNameError {}
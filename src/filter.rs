@@ -0,0 +1,189 @@
+use crate::graph::{Edge, EdgeKind, Node};
+use petgraph::algo::dijkstra;
+use petgraph::graph::{EdgeReference, Graph, NodeIndex};
+use petgraph::visit::{EdgeFiltered, Reversed};
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Finds the nodes with names matching `pattern`.
+fn matching(graph: &Graph<Node, Edge>, pattern: &Regex) -> Vec<NodeIndex> {
+    graph
+        .node_indices()
+        .filter(|idx| pattern.is_match(&graph[*idx].name().to_string_lossy()))
+        .collect()
+}
+
+/// Keeps only the nodes which are within `radius` hops of the given nodes, when `radius` is
+/// `None` everything reachable from them is kept.
+fn within_radius(
+    graph: &Graph<Node, Edge>,
+    starts: &[NodeIndex],
+    radius: Option<usize>,
+    upstream: bool,
+    downstream: bool,
+) -> HashSet<NodeIndex> {
+    let mut keep = HashSet::new();
+    let radius = radius.unwrap_or(usize::MAX);
+    let in_radius = |distance: &usize| *distance <= radius;
+    for start in starts {
+        keep.insert(*start);
+        if upstream {
+            let distances = dijkstra(Reversed(graph), *start, None, |_| 1);
+            keep.extend(
+                distances
+                    .iter()
+                    .filter(|(_, d)| in_radius(d))
+                    .map(|(n, _)| *n),
+            );
+        }
+        if downstream {
+            let distances = dijkstra(graph, *start, None, |_| 1);
+            keep.extend(
+                distances
+                    .iter()
+                    .filter(|(_, d)| in_radius(d))
+                    .map(|(n, _)| *n),
+            );
+        }
+    }
+    keep
+}
+
+fn retain_nodes(graph: &Graph<Node, Edge>, keep: &HashSet<NodeIndex>) -> Graph<Node, Edge> {
+    graph.filter_map(
+        |idx, node| {
+            if keep.contains(&idx) {
+                Some(node.clone())
+            } else {
+                None
+            }
+        },
+        |_, edge| Some(edge.clone()),
+    )
+}
+
+/// Trims the graph down to the nodes with names matching `pattern` and their ancestors
+/// (`upstream`) and/or descendants (`downstream`) up to `radius` hops away. Returns `None` if no
+/// nodes match `pattern`.
+pub fn focus(
+    graph: &Graph<Node, Edge>,
+    pattern: &Regex,
+    radius: Option<usize>,
+    upstream: bool,
+    downstream: bool,
+) -> Option<Graph<Node, Edge>> {
+    let starts = matching(graph, pattern);
+    if starts.is_empty() {
+        return None;
+    }
+    let keep = within_radius(graph, &starts, radius, upstream, downstream);
+    Some(retain_nodes(graph, &keep))
+}
+
+/// An end of the paths kept by [`between`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum PathEnd {
+    From,
+    To,
+}
+
+/// Trims the graph down to the nodes lying on a path of data edges from the nodes matching
+/// `from` to the nodes matching `to`. If only one end is given everything downstream of `from`
+/// or upstream of `to` is kept. Returns the end whose pattern doesn't match any nodes as an error.
+pub fn between(
+    graph: &Graph<Node, Edge>,
+    from: Option<&Regex>,
+    to: Option<&Regex>,
+) -> Result<Graph<Node, Edge>, PathEnd> {
+    let data = EdgeFiltered::from_fn(graph, |e: EdgeReference<Edge>| {
+        e.weight().kind() == EdgeKind::Data
+    });
+    let mut keep = graph.node_indices().collect::<HashSet<_>>();
+    if let Some(from) = from {
+        let starts = matching(graph, from);
+        if starts.is_empty() {
+            return Err(PathEnd::From);
+        }
+        let mut reached = HashSet::<NodeIndex>::new();
+        for start in starts {
+            reached.extend(dijkstra(&data, start, None, |_| 1).keys());
+        }
+        keep.retain(|idx| reached.contains(idx));
+    }
+    if let Some(to) = to {
+        let starts = matching(graph, to);
+        if starts.is_empty() {
+            return Err(PathEnd::To);
+        }
+        let mut reached = HashSet::<NodeIndex>::new();
+        for start in starts {
+            reached.extend(dijkstra(Reversed(&data), start, None, |_| 1).keys());
+        }
+        keep.retain(|idx| reached.contains(idx));
+    }
+    Ok(retain_nodes(graph, &keep))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphBuilder, GraphOptions};
+
+    /// `a -> b -> c -> d` with `e` also feeding `c` and a control edge from `a` to `d`.
+    fn chain() -> Graph<Node, Edge> {
+        let options = GraphOptions::default();
+        let mut builder = GraphBuilder::new(&options);
+        let data = || Edge::new(EdgeKind::Data, None, Some(0), Some(0));
+        for (from, to) in [("a", "b"), ("b", "c"), ("c", "d"), ("e", "c")] {
+            builder.add_edge((from, "Op"), (to, "Op"), data());
+        }
+        builder.add_edge(
+            ("a", "Op"),
+            ("d", "Op"),
+            Edge::new(EdgeKind::Control, None, None, None),
+        );
+        builder.build()
+    }
+
+    fn names(graph: &Graph<Node, Edge>) -> Vec<String> {
+        let mut names = graph
+            .node_weights()
+            .map(|node| node.name().display().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn pattern(pattern: &str) -> Regex {
+        Regex::new(&format!("^(?:{})$", pattern)).unwrap()
+    }
+
+    #[test]
+    fn focuses_within_radius() {
+        let graph = chain();
+        let focus = |radius, upstream, downstream| {
+            names(&focus(&graph, &pattern("c"), radius, upstream, downstream).unwrap())
+        };
+        assert_eq!(focus(Some(1), true, true), ["b", "c", "d", "e"]);
+        assert_eq!(focus(Some(0), true, true), ["c"]);
+        assert_eq!(focus(None, true, false), ["a", "b", "c", "e"]);
+        assert_eq!(focus(Some(1), false, true), ["c", "d"]);
+        assert!(super::focus(&graph, &pattern("z"), None, true, true).is_none());
+    }
+
+    #[test]
+    fn keeps_paths_between_ops() {
+        let graph = chain();
+        let between = |from: Option<&str>, to: Option<&str>| {
+            between(&graph, from.map(pattern).as_ref(), to.map(pattern).as_ref())
+                .map(|graph| names(&graph).join(" "))
+        };
+        let kept = |names: &str| Ok(names.to_string());
+        assert_eq!(between(Some("b"), Some("d")), kept("b c d"));
+        assert_eq!(between(Some("c"), None), kept("c d"));
+        assert_eq!(between(None, Some("c")), kept("a b c e"));
+        assert_eq!(between(Some("d"), Some("a")), kept(""));
+        assert_eq!(between(Some("z"), Some("d")), Err(PathEnd::From));
+        assert_eq!(between(Some("a"), Some("z")), Err(PathEnd::To));
+    }
+}
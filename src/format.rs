@@ -0,0 +1,152 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+
+/// Output formats the visualiser can produce.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Format {
+    Dot,
+    Svg,
+    Png,
+    Pdf,
+    /// The graph itself rather than a drawing of it, see [`crate::json`]
+    Json,
+    /// An interactive page for browsing the graph, see [`crate::html`]
+    Html,
+    /// A Mermaid flowchart, see [`crate::diagram`]
+    Mermaid,
+    /// A PlantUML diagram, see [`crate::diagram`]
+    PlantUml,
+    /// GraphML, see [`crate::xml`]
+    GraphMl,
+    /// GEXF, see [`crate::xml`]
+    Gexf,
+    /// A list of the ops for reading in a terminal, see [`crate::text`]
+    Text,
+    /// A summary table of the ops or blocks, see [`crate::summary`]
+    Summary,
+    /// The summary table in Markdown
+    Markdown,
+    /// The summary table as comma separated values
+    Csv,
+}
+
+impl Format {
+    /// Works out the format from a file extension, returning `None` if it's not recognised.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gv" => Some(Format::Dot),
+            "mmd" => Some(Format::Mermaid),
+            "puml" => Some(Format::PlantUml),
+            ext => ext.parse().ok(),
+        }
+    }
+
+    /// Renders a dot file into this format, for anything other than `Format::Dot` this runs the
+    /// graphviz `dot` executable so it needs to be installed and on the `PATH`. The formats which
+    /// aren't drawn by graphviz can't be rendered from a dot file, they're rendered from the graph
+    /// by their own modules.
+    pub fn render(&self, dot: &str) -> io::Result<Vec<u8>> {
+        match self {
+            Format::Dot => return Ok(dot.as_bytes().to_vec()),
+            Format::Json
+            | Format::Html
+            | Format::Mermaid
+            | Format::PlantUml
+            | Format::GraphMl
+            | Format::Gexf
+            | Format::Text
+            | Format::Summary
+            | Format::Markdown
+            | Format::Csv => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is rendered from the graph, not a dot file", self),
+                ))
+            }
+            _ => {}
+        }
+        let mut child = Command::new("dot")
+            .arg(format!("-T{}", self))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("unable to run graphviz `dot`, is it installed? ({})", e),
+                )
+            })?;
+        // Graphviz writes warnings while it's still reading, so the input is written from another
+        // thread while its output is collected. If it fails part way through the write fails with
+        // a broken pipe, so the error graphviz gave is reported instead
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let (written, output) = thread::scope(|scope| {
+            let writer = scope.spawn(move || stdin.write_all(dot.as_bytes()));
+            let output = child.wait_with_output();
+            let written = writer.join().expect("writing to graphviz doesn't panic");
+            (written, output)
+        });
+        let output = output?;
+        if output.status.success() {
+            written?;
+            Ok(output.stdout)
+        } else {
+            Err(io::Error::other(format!(
+                "graphviz failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "svg" => Ok(Format::Svg),
+            "png" => Ok(Format::Png),
+            "pdf" => Ok(Format::Pdf),
+            "json" => Ok(Format::Json),
+            "html" | "htm" => Ok(Format::Html),
+            "mermaid" => Ok(Format::Mermaid),
+            "plantuml" => Ok(Format::PlantUml),
+            "graphml" => Ok(Format::GraphMl),
+            "gexf" => Ok(Format::Gexf),
+            "text" | "txt" => Ok(Format::Text),
+            "summary" => Ok(Format::Summary),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "csv" => Ok(Format::Csv),
+            s => Err(format!("unsupported format '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Format::Dot => "dot",
+            Format::Svg => "svg",
+            Format::Png => "png",
+            Format::Pdf => "pdf",
+            Format::Json => "json",
+            Format::Html => "html",
+            Format::Mermaid => "mermaid",
+            Format::PlantUml => "plantuml",
+            Format::GraphMl => "graphml",
+            Format::Gexf => "gexf",
+            Format::Text => "text",
+            Format::Summary => "summary",
+            Format::Markdown => "markdown",
+            Format::Csv => "csv",
+        };
+        write!(f, "{}", s)
+    }
+}
@@ -0,0 +1,500 @@
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Options controlling how a model is turned into a graph.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct GraphOptions {
+    /// Maximum depth to recurse into nested blocks, anything deeper is collapsed into a block
+    pub max_depth: Option<usize>,
+    /// Whether to include control dependencies between ops
+    pub control_edges: bool,
+    /// Whether to draw function bodies in place of the ops calling them rather than once each
+    pub inline_functions: bool,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            control_edges: true,
+            inline_functions: false,
+        }
+    }
+}
+
+/// Formats a tensor shape, unknown dimensions are shown as `?`.
+pub(crate) fn format_dims(dims: &[Option<usize>]) -> String {
+    let dims = dims
+        .iter()
+        .map(|d| d.map_or_else(|| "?".to_string(), |d| d.to_string()))
+        .collect::<Vec<_>>();
+    format!("[{}]", dims.join(", "))
+}
+
+/// Formats a tensor shape which might have an unknown rank, shown as `?` so it can't be mistaken
+/// for a scalar.
+pub(crate) fn format_shape(dims: Option<&[Option<usize>]>) -> String {
+    dims.map_or_else(|| "?".to_string(), format_dims)
+}
+
+/// Aggregate information about the ops folded into a collapsed block.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct BlockSummary {
+    ops: usize,
+    op_types: BTreeMap<String, usize>,
+    parameters: usize,
+    inputs: Vec<Option<Vec<Option<usize>>>>,
+    outputs: Vec<Option<Vec<Option<usize>>>>,
+}
+
+impl BlockSummary {
+    /// Number of ops inside the block
+    pub fn ops(&self) -> usize {
+        self.ops
+    }
+
+    /// How many ops of each type are inside the block
+    pub fn op_types(&self) -> &BTreeMap<String, usize> {
+        &self.op_types
+    }
+
+    /// Total number of elements in the variables and constants inside the block
+    pub fn parameters(&self) -> usize {
+        self.parameters
+    }
+
+    /// Shapes of the tensors flowing into the block, `None` if the rank is unknown
+    pub fn inputs(&self) -> &[Option<Vec<Option<usize>>>] {
+        &self.inputs
+    }
+
+    /// Shapes of the tensors flowing out of the block, `None` if the rank is unknown
+    pub fn outputs(&self) -> &[Option<Vec<Option<usize>>>] {
+        &self.outputs
+    }
+
+    /// Adds an op to the block
+    pub fn add_op(&mut self, ty: &str, parameters: usize) {
+        self.ops += 1;
+        *self.op_types.entry(ty.to_string()).or_default() += 1;
+        self.parameters += parameters;
+    }
+
+    /// Adds the ops of another block collapsed into this one. The shapes are left for the graph
+    /// builder to fill in from the edges.
+    pub(crate) fn merge(&mut self, other: &BlockSummary) {
+        self.ops += other.ops;
+        for (ty, count) in &other.op_types {
+            *self.op_types.entry(ty.clone()).or_default() += count;
+        }
+        self.parameters += other.parameters;
+    }
+}
+
+impl fmt::Display for BlockSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ops, {} params", self.ops, self.parameters)?;
+        // Only the most common op types are listed to keep labels a readable size
+        let mut types = self.op_types.iter().collect::<Vec<_>>();
+        types.sort_by(|a, b| b.1.cmp(a.1));
+        let mut histogram = types
+            .iter()
+            .take(3)
+            .map(|(ty, count)| format!("{} x{}", ty, count))
+            .collect::<Vec<_>>();
+        if types.len() > 3 {
+            histogram.push(format!("+{} more", types.len() - 3));
+        }
+        if !histogram.is_empty() {
+            write!(f, "\n{}", histogram.join(", "))?;
+        }
+        let shapes = |dims: &[Option<Vec<Option<usize>>>]| {
+            dims.iter()
+                .map(|d| format_shape(d.as_deref()))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        if !self.inputs.is_empty() {
+            write!(f, "\nin: {}", shapes(&self.inputs))?;
+        }
+        if !self.outputs.is_empty() {
+            write!(f, "\nout: {}", shapes(&self.outputs))?;
+        }
+        Ok(())
+    }
+}
+
+/// An op in the model, or a block of ops when nested scopes have been collapsed.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Node {
+    name: PathBuf,
+    ty: String,
+    attributes: BTreeMap<String, String>,
+    summary: Option<BlockSummary>,
+    region: Vec<String>,
+    output_dims: Vec<Option<Vec<Option<usize>>>>,
+    parameters: usize,
+}
+
+impl Node {
+    pub fn new(name: impl Into<PathBuf>, ty: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ty: ty.into(),
+            attributes: BTreeMap::new(),
+            summary: None,
+            region: vec![],
+            output_dims: vec![],
+            parameters: 0,
+        }
+    }
+
+    /// Name of the op, each name scope is a component of the path
+    pub fn name(&self) -> &Path {
+        &self.name
+    }
+
+    /// Op type, or `"Block"` if this node is a collapsed scope
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+
+    /// Extra information about the op, depending on the model format
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.attributes
+    }
+
+    /// Summary of the ops inside a collapsed block, `None` for nodes which are a single op
+    pub fn summary(&self) -> Option<&BlockSummary> {
+        self.summary.as_ref()
+    }
+
+    /// Summary of the ops inside the block, creating an empty one if the node isn't a block yet
+    pub fn summary_mut(&mut self) -> &mut BlockSummary {
+        self.summary.get_or_insert_with(BlockSummary::default)
+    }
+
+    /// Number of parameters in the op, or the total in all the ops inside a collapsed block
+    pub fn parameters(&self) -> usize {
+        self.summary
+            .as_ref()
+            .map_or(self.parameters, BlockSummary::parameters)
+    }
+
+    /// Sets the number of parameters in the op, blocks take theirs from their summary instead
+    pub fn set_parameters(&mut self, parameters: usize) {
+        self.parameters = parameters;
+    }
+
+    /// Labels of the control flow regions the op is inside, such as loops or the branches of a
+    /// conditional, outermost first
+    pub fn region(&self) -> &[String] {
+        &self.region
+    }
+
+    pub fn region_mut(&mut self) -> &mut Vec<String> {
+        &mut self.region
+    }
+
+    /// Shapes of each output of the op, empty for collapsed blocks or if the model doesn't record
+    /// them
+    pub fn output_dims(&self) -> &[Option<Vec<Option<usize>>>] {
+        &self.output_dims
+    }
+
+    pub fn output_dims_mut(&mut self) -> &mut Vec<Option<Vec<Option<usize>>>> {
+        &mut self.output_dims
+    }
+
+    /// Collapses the node into the block containing it if the name is deeper than `max_depth`
+    pub fn limit_depth(&mut self, max_depth: usize) {
+        let depth = self.name.components().count();
+        for _ in max_depth..depth {
+            self.name.pop();
+        }
+        if max_depth < depth {
+            self.ty = "Block".to_string();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    /// A tensor flowing from one op to another
+    Data,
+    /// A control dependency, no data flows along it
+    Control,
+    /// A tensor going from the end of a loop back to its start for the next iteration
+    Back,
+    /// From an op calling a function to the function's body, no data flows along it
+    Call,
+}
+
+/// A connection between two nodes, going from the producing op to the consuming op.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Edge {
+    kind: EdgeKind,
+    dim: Option<Vec<Option<usize>>>,
+    input_index: Option<usize>,
+    output_index: Option<usize>,
+}
+
+impl Edge {
+    pub fn new(
+        kind: EdgeKind,
+        dim: Option<Vec<Option<usize>>>,
+        input_index: Option<usize>,
+        output_index: Option<usize>,
+    ) -> Self {
+        Self {
+            kind,
+            dim,
+            input_index,
+            output_index,
+        }
+    }
+
+    pub fn kind(&self) -> EdgeKind {
+        self.kind
+    }
+
+    /// Shape of the tensor with `None` for unknown dimensions, or `None` if the rank is unknown
+    pub fn dim(&self) -> Option<&[Option<usize>]> {
+        self.dim.as_deref()
+    }
+
+    /// Index of the input on the consuming op
+    pub fn input_index(&self) -> Option<usize> {
+        self.input_index
+    }
+
+    /// Index of the output on the producing op
+    pub fn output_index(&self) -> Option<usize> {
+        self.output_index
+    }
+}
+
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_shape(self.dim()))
+    }
+}
+
+/// Builds up the graph for a model, collapsing nodes deeper than the maximum depth and skipping
+/// duplicate edges. Shared by all the model front ends.
+pub(crate) struct GraphBuilder<'a> {
+    options: &'a GraphOptions,
+    graph: Graph<Node, Edge>,
+    nodes: HashMap<(PathBuf, String), NodeIndex>,
+}
+
+impl<'a> GraphBuilder<'a> {
+    pub(crate) fn new(options: &'a GraphOptions) -> Self {
+        Self {
+            options,
+            graph: Graph::new(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    fn node(&self, name: &str, ty: &str) -> Node {
+        let mut node = Node::new(name, ty);
+        if let Some(max_depth) = self.options.max_depth {
+            node.limit_depth(max_depth);
+        }
+        node
+    }
+
+    fn node_index(&mut self, name: &str, ty: &str) -> NodeIndex {
+        let node = self.node(name, ty);
+        let graph = &mut self.graph;
+        *self
+            .nodes
+            .entry((node.name.clone(), node.ty.clone()))
+            .or_insert_with(|| graph.add_node(node))
+    }
+
+    /// Adds an edge from the producing op `from` to the consuming op `to`, each given as a name
+    /// and op type. Edges inside a collapsed block or repeating an existing edge between the same
+    /// ports are dropped.
+    pub(crate) fn add_edge(&mut self, from: (&str, &str), to: (&str, &str), edge: Edge) {
+        if edge.kind == EdgeKind::Control && !self.options.control_edges {
+            return;
+        }
+        let from = self.node_index(from.0, from.1);
+        let to = self.node_index(to.0, to.1);
+        let exists = self.graph.edges_connecting(from, to).any(|e| {
+            let e = e.weight();
+            e.kind == edge.kind
+                && e.output_index == edge.output_index
+                && e.input_index == edge.input_index
+        });
+        if from != to && !exists {
+            self.graph.add_edge(from, to, edge);
+        }
+    }
+
+    /// Adds an op and records its parameters, adding the op to the summary of the block it's
+    /// collapsed into if it is collapsed. Ops without any edges are added too. This should be
+    /// called once per op and before setting its attributes or region.
+    pub(crate) fn add_op(&mut self, name: &str, ty: &str, parameters: usize) {
+        let idx = self.node_index(name, ty);
+        let node = &mut self.graph[idx];
+        if node.ty == "Block" {
+            node.summary_mut().add_op(ty, parameters);
+        } else {
+            node.parameters = parameters;
+        }
+    }
+
+    /// Adds a node from a graph which has already been built, such as one read back in from an
+    /// export. It's collapsed like an op if it's too deep, merging its summary into the block if
+    /// it's a block itself.
+    pub(crate) fn add_node(&mut self, node: &Node) {
+        let idx = self.node_index(&node.name.to_string_lossy(), &node.ty);
+        let target = &mut self.graph[idx];
+        match &node.summary {
+            Some(summary) => target.summary_mut().merge(summary),
+            None if target.ty == "Block" => target.summary_mut().add_op(&node.ty, node.parameters),
+            None => *target = node.clone(),
+        }
+    }
+
+    /// Sets the attributes of an op added with `add_op`, ops collapsed into a block are ignored.
+    pub(crate) fn set_attributes(
+        &mut self,
+        name: &str,
+        ty: &str,
+        attributes: &BTreeMap<String, String>,
+    ) {
+        let node = self.node(name, ty);
+        if node.ty == "Block" {
+            return;
+        }
+        if let Some(idx) = self.nodes.get(&(node.name, node.ty)) {
+            self.graph[*idx].attributes = attributes.clone();
+        }
+    }
+
+    /// Sets the control flow region of an op added with `add_op`, ops collapsed into a block are
+    /// ignored.
+    pub(crate) fn set_region(&mut self, name: &str, ty: &str, region: Vec<String>) {
+        let node = self.node(name, ty);
+        if node.ty == "Block" {
+            return;
+        }
+        if let Some(idx) = self.nodes.get(&(node.name, node.ty)) {
+            self.graph[*idx].region = region;
+        }
+    }
+
+    /// Sets the shapes of the outputs of an op added with `add_op`, ops collapsed into a block are
+    /// ignored.
+    pub(crate) fn set_output_dims(
+        &mut self,
+        name: &str,
+        ty: &str,
+        output_dims: Vec<Option<Vec<Option<usize>>>>,
+    ) {
+        let node = self.node(name, ty);
+        if node.ty == "Block" {
+            return;
+        }
+        if let Some(idx) = self.nodes.get(&(node.name, node.ty)) {
+            self.graph[*idx].output_dims = output_dims;
+        }
+    }
+
+    pub(crate) fn build(mut self) -> Graph<Node, Edge> {
+        summarise_block_edges(&mut self.graph);
+        self.graph
+    }
+}
+
+/// Fills in the input and output shapes of every block summary from the data edges entering and
+/// leaving the block.
+fn summarise_block_edges(graph: &mut Graph<Node, Edge>) {
+    for node in graph.node_indices() {
+        if graph[node].summary.is_none() {
+            continue;
+        }
+        let dims = |dir| {
+            graph
+                .edges_directed(node, dir)
+                .filter(|e| e.weight().kind == EdgeKind::Data)
+                .map(|e| e.weight().dim.clone())
+                .collect::<Vec<_>>()
+        };
+        let inputs = dims(Direction::Incoming);
+        let outputs = dims(Direction::Outgoing);
+        let summary = graph[node].summary_mut();
+        summary.inputs = inputs;
+        summary.outputs = outputs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(output_index: usize, input_index: usize) -> Edge {
+        Edge::new(
+            EdgeKind::Data,
+            Some(vec![Some(2)]),
+            Some(input_index),
+            Some(output_index),
+        )
+    }
+
+    #[test]
+    fn keeps_each_output_of_an_op() {
+        let options = GraphOptions::default();
+        let mut builder = GraphBuilder::new(&options);
+        builder.add_edge(("split", "Split"), ("cat", "ConcatV2"), data(0, 0));
+        builder.add_edge(("split", "Split"), ("cat", "ConcatV2"), data(1, 1));
+        builder.add_edge(("split", "Split"), ("cat", "ConcatV2"), data(1, 1));
+        let graph = builder.build();
+
+        let mut ports = graph
+            .edge_weights()
+            .map(|e| (e.output_index(), e.input_index()))
+            .collect::<Vec<_>>();
+        ports.sort();
+        assert_eq!(ports, vec![(Some(0), Some(0)), (Some(1), Some(1))]);
+    }
+
+    #[test]
+    fn keeps_ops_without_edges() {
+        let options = GraphOptions::default();
+        let mut builder = GraphBuilder::new(&options);
+        builder.add_op("init", "NoOp", 0);
+        let graph = builder.build();
+
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(graph[NodeIndex::new(0)].ty(), "NoOp");
+    }
+
+    #[test]
+    fn drops_edges_inside_a_block() {
+        let options = GraphOptions {
+            max_depth: Some(1),
+            ..GraphOptions::default()
+        };
+        let mut builder = GraphBuilder::new(&options);
+        builder.add_edge(("net/split", "Split"), ("net/cat", "ConcatV2"), data(0, 0));
+        builder.add_edge(("net/split", "Split"), ("net/cat", "ConcatV2"), data(1, 1));
+        let graph = builder.build();
+
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(graph.edge_count(), 0);
+    }
+}
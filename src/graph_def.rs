@@ -0,0 +1,727 @@
+//! Front end which decodes tensorflow GraphDef and SavedModel protobufs directly, without the
+//! TensorFlow runtime. Any op can be drawn, including custom ops the runtime doesn't have
+//! registered, but shapes are limited to what's recorded in the GraphDef such as
+//! `_output_shapes`.
+use crate::error::{Error, InvalidNameError};
+use crate::graph::format_shape;
+use crate::model::{Branch, Function, ModelGraph, NameDecoder, Op, Port};
+use crate::pbtxt;
+use prost::Message;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+/// A decoded GraphDef.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Model(proto::GraphDef);
+
+impl Model {
+    pub fn decode(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
+        proto::GraphDef::decode(bytes).map(Model)
+    }
+
+    /// Loads a GraphDef in either the binary or text format.
+    pub fn load(input: &Path) -> Result<Self, Error> {
+        let bytes = read_binary(input, pbtxt::graph_def_to_binary)?;
+        Self::decode(&bytes).map_err(|e| parse_error(input, e.to_string()))
+    }
+
+    /// Loads the graph of the meta graph in a SavedModel directory with exactly the given tags,
+    /// like the TensorFlow loader.
+    pub fn load_saved_model(dir: &Path, tags: &[String]) -> Result<Self, Error> {
+        let mut input = dir.join("saved_model.pb");
+        if !input.is_file() {
+            input.set_extension("pbtxt");
+        }
+        let bytes = read_binary(&input, pbtxt::saved_model_to_binary)?;
+        let saved_model = proto::SavedModel::decode(&bytes[..])
+            .map_err(|e| parse_error(&input, e.to_string()))?;
+        let wanted = tags.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        let mut available = vec![];
+        for meta_graph in saved_model.meta_graphs {
+            let meta_tags = meta_graph
+                .meta_info_def
+                .map(|info| info.tags)
+                .unwrap_or_default();
+            if meta_tags
+                .iter()
+                .map(String::as_str)
+                .collect::<BTreeSet<_>>()
+                == wanted
+            {
+                return Ok(Model(meta_graph.graph_def.unwrap_or_default()));
+            }
+            available.push(meta_tags);
+        }
+        Err(Error::MetaGraphNotFound {
+            path: dir.to_path_buf(),
+            tags: tags.to_vec(),
+            available,
+        })
+    }
+}
+
+fn parse_error(input: &Path, message: String) -> Error {
+    Error::Parse {
+        path: input.to_path_buf(),
+        message,
+    }
+}
+
+/// Reads a protobuf file, converting it to the binary encoding if it's in the text format.
+fn read_binary(
+    input: &Path,
+    to_binary: fn(&str) -> Result<Vec<u8>, pbtxt::ParseError>,
+) -> Result<Vec<u8>, Error> {
+    let bytes = fs::read(input).map_err(|source| Error::Io {
+        path: input.to_path_buf(),
+        source,
+    })?;
+    if !pbtxt::is_text(&bytes) {
+        return Ok(bytes);
+    }
+    let text = String::from_utf8(bytes).map_err(|e| parse_error(input, e.to_string()))?;
+    to_binary(&text).map_err(|e| parse_error(input, e.to_string()))
+}
+
+fn shape_dims(shape: &proto::TensorShapeProto) -> Option<Vec<Option<usize>>> {
+    if shape.unknown_rank {
+        return None;
+    }
+    let dims = shape
+        .dim
+        .iter()
+        .map(|d| usize::try_from(d.size).ok())
+        .collect();
+    Some(dims)
+}
+
+/// Value of an attribute of a node, if there's more than one with the name the last one is used
+/// like any other protobuf map.
+fn attr<'a>(node: &'a proto::NodeDef, name: &str) -> Option<&'a proto::attr_value::Value> {
+    node.attr
+        .iter()
+        .rev()
+        .find(|entry| entry.key == name.as_bytes())
+        .and_then(|entry| entry.value.as_ref()?.value.as_ref())
+}
+
+/// Shapes of each output of a node, taken from `_output_shapes` if the graph was exported with
+/// them and otherwise from the attributes of the few ops where the shape is known up front.
+fn output_shapes(node: &proto::NodeDef) -> Vec<Option<Vec<Option<usize>>>> {
+    use self::proto::attr_value::Value;
+    let attr = |name| attr(node, name);
+    if let Some(Value::List(list)) = attr("_output_shapes") {
+        return list.shape.iter().map(shape_dims).collect();
+    }
+    match (attr("shape"), attr("value")) {
+        (Some(Value::Shape(shape)), _) => vec![shape_dims(shape)],
+        (_, Some(Value::Tensor(tensor))) => tensor
+            .tensor_shape
+            .as_ref()
+            .map(|shape| vec![shape_dims(shape)])
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Number of parameters held by a variable or constant op, zero for any other op or if the shape
+/// isn't fully known. This is also used for graphs loaded by the TensorFlow runtime.
+fn parameter_count(
+    node: &proto::NodeDef,
+    ty: &str,
+    shapes: &[Option<Vec<Option<usize>>>],
+) -> usize {
+    use self::proto::attr_value::Value;
+    let dims = match (ty, attr(node, "shape")) {
+        ("Const", _) => shapes.first().cloned().flatten(),
+        ("Variable" | "VariableV2" | "VarHandleOp", Some(Value::Shape(shape))) => shape_dims(shape),
+        _ => None,
+    };
+    dims.and_then(|dims| dims.into_iter().product::<Option<usize>>())
+        .unwrap_or(0)
+}
+
+/// Short descriptions of the scalar attributes of a node, internal attributes (starting with
+/// `_`) and tensor values are left out.
+fn attributes(
+    node: &proto::NodeDef,
+    names: &mut NameDecoder,
+) -> Result<BTreeMap<String, String>, InvalidNameError> {
+    use self::proto::attr_value::Value;
+    let mut attributes = BTreeMap::new();
+    for entry in &node.attr {
+        if entry.key.starts_with(b"_") {
+            continue;
+        }
+        let value = match entry.value.as_ref().and_then(|a| a.value.as_ref()) {
+            Some(value) => value,
+            None => continue,
+        };
+        let value = match value {
+            Value::S(s) if s.len() <= 64 => match std::str::from_utf8(s) {
+                Ok(s) => s.to_string(),
+                Err(_) => continue,
+            },
+            Value::I(i) => i.to_string(),
+            Value::F(f) => f.to_string(),
+            Value::B(b) => b.to_string(),
+            Value::Type(ty) => pbtxt::data_type_name(*ty),
+            Value::Shape(shape) => format_shape(shape_dims(shape).as_deref()),
+            Value::List(list) if !list.i.is_empty() => format!("{:?}", list.i),
+            Value::Func(func) => names.decode(&func.name)?,
+            _ => continue,
+        };
+        attributes.insert(names.decode(&entry.key)?, value);
+    }
+    Ok(attributes)
+}
+
+/// A `NodeDef` input split into its parts.
+#[derive(Debug, Eq, PartialEq)]
+struct Input<'a> {
+    node: &'a [u8],
+    output_arg: Option<&'a [u8]>,
+    index: usize,
+    control: bool,
+}
+
+/// Splits a `NodeDef` input into the producing node and output index. Inputs are `node`,
+/// `node:index` or `^node` for a control dependency, inside functions they can also be
+/// `node:output_arg:index` in which case the index is within that output argument.
+fn parse_input(input: &[u8]) -> Input<'_> {
+    if let Some(node) = input.strip_prefix(b"^") {
+        return Input {
+            node,
+            output_arg: None,
+            index: 0,
+            control: true,
+        };
+    }
+    let mut parts = input.splitn(3, |b| *b == b':');
+    let node = parts.next().unwrap_or_default();
+    let (output_arg, index) = match (parts.next(), parts.next()) {
+        (Some(arg), Some(index)) => (Some(arg), Some(index)),
+        (index, _) => (None, index),
+    };
+    let index = index
+        .and_then(|i| std::str::from_utf8(i).ok())
+        .and_then(|i| i.parse().ok())
+        .unwrap_or(0);
+    Input {
+        node,
+        output_arg,
+        index,
+        control: false,
+    }
+}
+
+/// The function called by a node, either through a call op or by using the function's name as
+/// the op type.
+fn called_function(node: &proto::NodeDef, functions: &HashSet<&[u8]>) -> Option<Vec<u8>> {
+    use self::proto::attr_value::Value;
+    match attr(node, "f") {
+        Some(Value::Func(func))
+            if node.op == b"PartitionedCall" || node.op == b"StatefulPartitionedCall" =>
+        {
+            Some(func.name.clone())
+        }
+        _ if functions.contains(node.op.as_slice()) => Some(node.op.clone()),
+        _ => None,
+    }
+}
+
+/// The functions run by TF2 control flow ops, `While` loops have a `cond` and `body` while `If`
+/// and `Case` have a function for each branch.
+fn branches(
+    node: &proto::NodeDef,
+    names: &mut NameDecoder,
+) -> Result<Vec<Branch>, InvalidNameError> {
+    use self::proto::attr_value::Value;
+    let attr = |name| attr(node, name);
+    let func = |name| match attr(name) {
+        Some(Value::Func(func)) => vec![func.name.as_slice()],
+        _ => vec![],
+    };
+    let (functions, labels, first_input, loops) = match node.op.as_slice() {
+        b"While" | b"StatelessWhile" => {
+            let functions = [func("cond"), func("body")].concat();
+            (
+                functions,
+                vec!["cond".to_string(), "body".to_string()],
+                0,
+                vec![false, true],
+            )
+        }
+        b"If" | b"StatelessIf" => {
+            let functions = [func("then_branch"), func("else_branch")].concat();
+            (
+                functions,
+                vec!["then".to_string(), "else".to_string()],
+                1,
+                vec![],
+            )
+        }
+        b"Case" | b"StatelessCase" => {
+            let functions = match attr("branches") {
+                Some(Value::List(list)) => list.func.iter().map(|f| f.name.as_slice()).collect(),
+                _ => vec![],
+            };
+            let labels = (0..functions.len())
+                .map(|i| format!("branch_{}", i))
+                .collect();
+            (functions, labels, 1, vec![])
+        }
+        _ => return Ok(vec![]),
+    };
+    // A branch is only drawn if all of the functions are there so the labels line up
+    if functions.len() != labels.len() {
+        return Ok(vec![]);
+    }
+    functions
+        .into_iter()
+        .zip(labels)
+        .enumerate()
+        .map(|(i, (function, label))| {
+            Ok(Branch {
+                label,
+                function: names.decode(function)?,
+                first_input,
+                loops: loops.get(i).copied().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// Builds the ops for the nodes of a graph or function body.
+fn node_ops(
+    nodes: &[proto::NodeDef],
+    functions: &HashSet<&[u8]>,
+    names: &mut NameDecoder,
+) -> Result<Vec<Op>, InvalidNameError> {
+    let shapes = nodes
+        .iter()
+        .map(|node| (node.name.as_slice(), output_shapes(node)))
+        .collect::<HashMap<_, _>>();
+
+    let mut ops = vec![];
+    for node in nodes {
+        let ty = names.decode(&node.op)?;
+        let mut inputs = vec![];
+        let mut control_inputs = vec![];
+        for input in &node.input {
+            let input = parse_input(input);
+            if input.control {
+                control_inputs.push(names.decode(input.node)?);
+            } else {
+                // The shapes are listed by position, which isn't known for an output argument
+                let dim = match input.output_arg {
+                    Some(_) => None,
+                    None => shapes
+                        .get(input.node)
+                        .and_then(|s| s.get(input.index))
+                        .cloned()
+                        .flatten(),
+                };
+                inputs.push(Port {
+                    op: names.decode(input.node)?,
+                    index: input.index,
+                    output_arg: input.output_arg.map(|a| names.decode(a)).transpose()?,
+                    dim,
+                });
+            }
+        }
+        let output_dims = shapes[node.name.as_slice()].clone();
+        ops.push(Op {
+            name: names.decode(&node.name)?,
+            parameters: parameter_count(node, &ty, &output_dims),
+            output_dims,
+            ty,
+            inputs,
+            control_inputs,
+            attributes: attributes(node, names)?,
+            call: called_function(node, functions)
+                .map(|f| names.decode(&f))
+                .transpose()?,
+            branches: branches(node, names)?,
+        });
+    }
+    Ok(ops)
+}
+
+impl Model {
+    fn function_names(&self) -> HashSet<&[u8]> {
+        self.0
+            .library
+            .iter()
+            .flat_map(|library| &library.function)
+            .filter_map(|function| function.signature.as_ref())
+            .map(|signature| signature.name.as_slice())
+            .collect()
+    }
+}
+
+impl ModelGraph for Model {
+    fn ops(&self, names: &mut NameDecoder) -> Result<Vec<Op>, InvalidNameError> {
+        node_ops(&self.0.node, &self.function_names(), names)
+    }
+
+    /// Functions in the library, each argument is an op of type `Input`.
+    fn functions(&self, names: &mut NameDecoder) -> Result<Vec<Function>, InvalidNameError> {
+        let function_names = self.function_names();
+        let mut functions = vec![];
+        for function in self.0.library.iter().flat_map(|library| &library.function) {
+            let signature = match function.signature.as_ref() {
+                Some(signature) => signature,
+                None => continue,
+            };
+            let mut ops = vec![];
+            let mut inputs = vec![];
+            for arg in &signature.input_arg {
+                let name = names.decode(&arg.name)?;
+                inputs.push(name.clone());
+                ops.push(Op {
+                    name,
+                    ty: "Input".to_string(),
+                    inputs: vec![],
+                    control_inputs: vec![],
+                    output_dims: vec![],
+                    parameters: 0,
+                    attributes: BTreeMap::new(),
+                    call: None,
+                    branches: vec![],
+                });
+            }
+            ops.extend(node_ops(&function.node_def, &function_names, names)?);
+            let mut output_args = vec![];
+            let mut outputs = vec![];
+            for arg in &signature.output_arg {
+                let ret = function.ret.iter().rev().find(|ret| ret.key == arg.name);
+                let input = parse_input(ret.map_or(&[], |ret| ret.value.as_slice()));
+                output_args.push(names.decode(&arg.name)?);
+                outputs.push(Port {
+                    op: names.decode(input.node)?,
+                    index: input.index,
+                    output_arg: input.output_arg.map(|a| names.decode(a)).transpose()?,
+                    dim: None,
+                });
+            }
+            functions.push(Function {
+                name: names.decode(&signature.name)?,
+                inputs,
+                output_args,
+                outputs,
+                ops,
+            });
+        }
+        Ok(functions)
+    }
+}
+
+/// The subset of the tensorflow protobuf schema (saved_model.proto, graph.proto, function.proto,
+/// attr_value.proto and friends) needed to build the graph. Names are kept as bytes so invalid
+/// UTF-8 doesn't fail decoding.
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SavedModel {
+        #[prost(message, repeated, tag = "2")]
+        pub meta_graphs: Vec<MetaGraphDef>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MetaGraphDef {
+        #[prost(message, optional, tag = "1")]
+        pub meta_info_def: Option<MetaInfoDef>,
+        #[prost(message, optional, tag = "2")]
+        pub graph_def: Option<GraphDef>,
+    }
+
+    /// `MetaGraphDef.MetaInfoDef` in the schema.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MetaInfoDef {
+        #[prost(string, repeated, tag = "4")]
+        pub tags: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GraphDef {
+        #[prost(message, repeated, tag = "1")]
+        pub node: Vec<NodeDef>,
+        #[prost(message, optional, tag = "2")]
+        pub library: Option<FunctionDefLibrary>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FunctionDefLibrary {
+        #[prost(message, repeated, tag = "1")]
+        pub function: Vec<FunctionDef>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FunctionDef {
+        #[prost(message, optional, tag = "1")]
+        pub signature: Option<OpDef>,
+        #[prost(message, repeated, tag = "3")]
+        pub node_def: Vec<NodeDef>,
+        /// Maps output argument names to the body tensors returned for them
+        #[prost(message, repeated, tag = "4")]
+        pub ret: Vec<StringEntry>,
+    }
+
+    /// An entry of a `map<string, string>`, maps are read as their entries so the keys can be
+    /// bytes.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StringEntry {
+        #[prost(bytes, tag = "1")]
+        pub key: Vec<u8>,
+        #[prost(bytes, tag = "2")]
+        pub value: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct OpDef {
+        #[prost(bytes, tag = "1")]
+        pub name: Vec<u8>,
+        #[prost(message, repeated, tag = "2")]
+        pub input_arg: Vec<ArgDef>,
+        #[prost(message, repeated, tag = "3")]
+        pub output_arg: Vec<ArgDef>,
+    }
+
+    /// `OpDef.ArgDef` in the schema.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ArgDef {
+        #[prost(bytes, tag = "1")]
+        pub name: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NodeDef {
+        #[prost(bytes, tag = "1")]
+        pub name: Vec<u8>,
+        #[prost(bytes, tag = "2")]
+        pub op: Vec<u8>,
+        #[prost(bytes, repeated, tag = "3")]
+        pub input: Vec<Vec<u8>>,
+        #[prost(message, repeated, tag = "5")]
+        pub attr: Vec<AttrEntry>,
+    }
+
+    /// An entry of a `map<string, AttrValue>`.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AttrEntry {
+        #[prost(bytes, tag = "1")]
+        pub key: Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub value: Option<AttrValue>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AttrValue {
+        #[prost(oneof = "attr_value::Value", tags = "1, 2, 3, 4, 5, 6, 7, 8, 10")]
+        pub value: Option<attr_value::Value>,
+    }
+
+    pub mod attr_value {
+        /// The kinds of attribute which are read, `placeholder` is skipped
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Value {
+            #[prost(message, tag = "1")]
+            List(super::ListValue),
+            #[prost(bytes, tag = "2")]
+            S(Vec<u8>),
+            #[prost(int64, tag = "3")]
+            I(i64),
+            #[prost(float, tag = "4")]
+            F(f32),
+            #[prost(bool, tag = "5")]
+            B(bool),
+            /// A `DataType` enum value
+            #[prost(int32, tag = "6")]
+            Type(i32),
+            #[prost(message, tag = "7")]
+            Shape(super::TensorShapeProto),
+            #[prost(message, tag = "8")]
+            Tensor(super::TensorProto),
+            #[prost(message, tag = "10")]
+            Func(super::NameAttrList),
+        }
+    }
+
+    /// A function and the attributes it's instantiated with, only the name is needed.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NameAttrList {
+        #[prost(bytes, tag = "1")]
+        pub name: Vec<u8>,
+    }
+
+    /// `AttrValue.ListValue` in the schema.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListValue {
+        #[prost(int64, repeated, tag = "3")]
+        pub i: Vec<i64>,
+        #[prost(message, repeated, tag = "7")]
+        pub shape: Vec<TensorShapeProto>,
+        #[prost(message, repeated, tag = "9")]
+        pub func: Vec<NameAttrList>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorShapeProto {
+        #[prost(message, repeated, tag = "2")]
+        pub dim: Vec<Dim>,
+        #[prost(bool, tag = "3")]
+        pub unknown_rank: bool,
+    }
+
+    /// `TensorShapeProto.Dim` in the schema, unknown sizes are -1.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Dim {
+        #[prost(int64, tag = "1")]
+        pub size: i64,
+    }
+
+    /// Only the shape of tensors is needed, the values are skipped.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorProto {
+        #[prost(int32, tag = "1")]
+        pub dtype: i32,
+        #[prost(message, optional, tag = "2")]
+        pub tensor_shape: Option<TensorShapeProto>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inputs() {
+        let input = |node, output_arg, index, control| Input {
+            node,
+            output_arg,
+            index,
+            control,
+        };
+        assert_eq!(parse_input(b"x"), input(b"x", None, 0, false));
+        assert_eq!(parse_input(b"x:1"), input(b"x", None, 1, false));
+        assert_eq!(parse_input(b"^x"), input(b"x", None, 0, true));
+        assert_eq!(
+            parse_input(b"bn:batch_mean:0"),
+            input(b"bn", Some(&b"batch_mean"[..]), 0, false)
+        );
+    }
+
+    fn model(text: &str) -> Model {
+        Model::decode(&pbtxt::graph_def_to_binary(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn only_looks_up_shapes_by_position() {
+        let model = model(
+            r#"
+            node { name: "bn" op: "FusedBatchNorm" attr { key: "_output_shapes" value { list {
+              shape { dim { size: 4 } } shape { dim { size: 2 } } } } } }
+            node { name: "a" op: "Identity" input: "bn:1" }
+            node { name: "b" op: "Identity" input: "bn:batch_mean:0" }
+            "#,
+        );
+        let ops = model.ops(&mut NameDecoder::new(false)).unwrap();
+        assert_eq!(ops[1].inputs[0].dim, Some(vec![Some(2)]));
+        assert_eq!(ops[2].inputs[0].dim, None);
+        assert_eq!(ops[2].inputs[0].output_arg.as_deref(), Some("batch_mean"));
+    }
+
+    fn ops(text: &str) -> Vec<Op> {
+        model(text).ops(&mut NameDecoder::new(false)).unwrap()
+    }
+
+    #[test]
+    fn reads_output_shapes() {
+        let ops = ops(r#"
+            node { name: "recorded" op: "Placeholder"
+              attr { key: "shape" value { shape { dim { size: 9 } } } }
+              attr { key: "_output_shapes" value { list {
+                shape { dim { size: -1 } dim { size: 3 } } shape { unknown_rank: true } } } } }
+            node { name: "placeholder" op: "Placeholder"
+              attr { key: "shape" value { shape { dim { size: 2 } } } } }
+            node { name: "const" op: "Const"
+              attr { key: "value" value { tensor { tensor_shape { dim { size: 3 } dim { size: 4 } } } } } }
+            node { name: "scalar" op: "Const" attr { key: "value" value { tensor { tensor_shape { } } } } }
+            node { name: "add" op: "Add" input: "const" input: "placeholder" }
+        "#);
+        let shapes = ops
+            .iter()
+            .map(|op| op.output_dims.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shapes,
+            vec![
+                vec![Some(vec![None, Some(3)]), None],
+                vec![Some(vec![Some(2)])],
+                vec![Some(vec![Some(3), Some(4)])],
+                vec![Some(vec![])],
+                vec![],
+            ]
+        );
+        assert_eq!(ops[4].inputs[0].dim, Some(vec![Some(3), Some(4)]));
+    }
+
+    #[test]
+    fn counts_parameters() {
+        let ops = ops(r#"
+            node { name: "const" op: "Const"
+              attr { key: "value" value { tensor { tensor_shape { dim { size: 3 } dim { size: 4 } } } } } }
+            node { name: "scalar" op: "Const" attr { key: "value" value { tensor { tensor_shape { } } } } }
+            node { name: "variable" op: "VariableV2"
+              attr { key: "shape" value { shape { dim { size: 4 } dim { size: 5 } } } } }
+            node { name: "handle" op: "VarHandleOp"
+              attr { key: "shape" value { shape { dim { size: 2 } } } } }
+            node { name: "dynamic" op: "VarHandleOp"
+              attr { key: "shape" value { shape { dim { size: -1 } dim { size: 5 } } } } }
+            node { name: "placeholder" op: "Placeholder"
+              attr { key: "shape" value { shape { dim { size: 2 } } } } }
+        "#);
+        let parameters = ops.iter().map(|op| op.parameters).collect::<Vec<_>>();
+        assert_eq!(parameters, vec![12, 1, 20, 2, 0, 0]);
+    }
+
+    #[test]
+    fn loads_the_meta_graph_with_matching_tags() {
+        let dir = std::env::temp_dir().join(format!("nn-visualiser-{}-tags", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("saved_model.pbtxt"),
+            r#"
+            meta_graphs { meta_info_def { tags: "serve" } graph_def { node { name: "a" op: "A" } } }
+            meta_graphs { meta_info_def { tags: "serve" tags: "gpu" }
+              graph_def { node { name: "b" op: "B" } } }
+            "#,
+        )
+        .unwrap();
+        let load = |tags: &[&str]| {
+            let tags = tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+            Model::load_saved_model(&dir, &tags)
+        };
+        let first_op = |model: Model| model.0.node[0].name.clone();
+
+        let serve = load(&["serve"]);
+        let gpu = load(&["gpu", "serve"]);
+        let train = load(&["train"]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first_op(serve.unwrap()), b"a");
+        assert_eq!(first_op(gpu.unwrap()), b"b");
+        match train {
+            Err(Error::MetaGraphNotFound { available, .. }) => assert_eq!(
+                available,
+                vec![
+                    vec!["serve".to_string()],
+                    vec!["serve".to_string(), "gpu".to_string()]
+                ]
+            ),
+            other => panic!("expected the meta graph not to be found, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Renders a graph as a single HTML page for browsing large models, with the graph embedded so it
+//! works offline.
+//!
+//! The page lays the graph out itself, so ops can be searched for, clicked on to see their type,
+//! attributes and shapes, and name scopes can be collapsed into blocks and expanded again.
+use crate::dot::node_style;
+use crate::graph::{Edge, Node};
+use crate::json;
+use petgraph::graph::Graph;
+use std::collections::BTreeMap;
+
+const VIEWER: &str = include_str!("viewer.html");
+
+/// Renders the page for a graph, name scopes deeper than `max_depth` start off collapsed. The graph
+/// should be generated without a `max_depth` so the collapsed scopes can still be expanded.
+pub fn render(graph: &Graph<Node, Edge>, max_depth: Option<usize>) -> String {
+    // The colours are the same as the graphviz output, collapsed scopes are drawn as blocks
+    let colours = graph
+        .node_weights()
+        .map(Node::ty)
+        .chain(Some("Block"))
+        .map(|ty| (ty, node_style(ty).1))
+        .collect::<BTreeMap<_, _>>();
+    let colours = serde_json::to_string(&colours).expect("a map of strings can be serialised");
+    // `<` only appears inside JSON strings, escaping it means names can't close the script element
+    let colours = colours.replace('<', "\\u003c");
+    let graph = json::render(graph).replace('<', "\\u003c");
+    let max_depth = max_depth.map_or_else(|| "null".to_string(), |d| d.to_string());
+    VIEWER
+        .replacen("__MAX_DEPTH__", &max_depth, 1)
+        .replacen("__COLOURS__", &colours, 1)
+        .replacen("__GRAPH__", &graph, 1)
+}
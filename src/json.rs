@@ -0,0 +1,324 @@
+//! Exports graphs as JSON for other tools to consume, and reads them back in so an export can be
+//! rendered again later.
+//!
+//! The schema is a single object:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "nodes": [
+//!     {
+//!       "id": 0,
+//!       "name": "dense/MatMul",
+//!       "type": "MatMul",
+//!       "scope": ["dense"],
+//!       "attributes": { "transpose_a": "false" },
+//!       "region": ["while rnn/while"],
+//!       "output_shapes": [[1, null, 64]],
+//!       "parameters": 0,
+//!       "summary": null
+//!     }
+//!   ],
+//!   "edges": [
+//!     {
+//!       "source": 0,
+//!       "destination": 1,
+//!       "source_port": 0,
+//!       "destination_port": 1,
+//!       "kind": "data",
+//!       "shape": [1, null, 64]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! * `id` is what edges use to refer to a node, names aren't unique once scopes are collapsed
+//! * `scope` is the name scopes the op is inside, outermost first. It's only there for consumers'
+//!   convenience, the name is used when reading a graph back in
+//! * `region` is the control flow regions the op is inside, such as loops, outermost first
+//! * `output_shapes` is the shape of each output of the op, in the same form as an edge's `shape`.
+//!   It's empty for collapsed blocks or if the model doesn't record them
+//! * `parameters` is the number of parameters in the op, or all the ops in a collapsed block
+//! * `summary` is only set for collapsed blocks and has the `ops`, `op_types`, `parameters`,
+//!   `inputs` and `outputs` of the ops inside it
+//! * `source_port` and `destination_port` are the output index on the producing op and the input
+//!   index on the consuming op, `null` if the model doesn't have them
+//! * `kind` is `data`, `control`, `back` (a tensor going back to the start of a loop) or `call`
+//!   (from an op to the body of the function it calls)
+//! * `shape` is the tensor shape with `null` for unknown dimensions, or `null` if the rank is
+//!   unknown
+use crate::dot::name_scope;
+use crate::error::Error;
+use crate::graph::{BlockSummary, Edge, EdgeKind, GraphBuilder, GraphOptions, Node};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the schema, bumped whenever a change would break existing consumers.
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct JsonGraph {
+    version: u32,
+    nodes: Vec<JsonNode>,
+    edges: Vec<JsonEdge>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonNode {
+    id: usize,
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default)]
+    scope: Vec<String>,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+    #[serde(default)]
+    region: Vec<String>,
+    #[serde(default)]
+    output_shapes: Vec<Option<Vec<Option<usize>>>>,
+    #[serde(default)]
+    parameters: usize,
+    #[serde(default)]
+    summary: Option<BlockSummary>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEdge {
+    source: usize,
+    destination: usize,
+    source_port: Option<usize>,
+    destination_port: Option<usize>,
+    kind: EdgeKind,
+    #[serde(default)]
+    shape: Option<Vec<Option<usize>>>,
+}
+
+/// Renders the graph as JSON following the schema in the module docs.
+pub fn render(graph: &Graph<Node, Edge>) -> String {
+    let nodes = graph
+        .node_indices()
+        .map(|idx| {
+            let node = &graph[idx];
+            JsonNode {
+                id: idx.index(),
+                name: node.name().to_string_lossy().into_owned(),
+                ty: node.ty().to_string(),
+                scope: name_scope(node),
+                attributes: node.attributes().clone(),
+                region: node.region().to_vec(),
+                output_shapes: node.output_dims().to_vec(),
+                parameters: node.parameters(),
+                summary: node.summary().cloned(),
+            }
+        })
+        .collect();
+    let edges = graph
+        .edge_references()
+        .map(|edge| JsonEdge {
+            source: edge.source().index(),
+            destination: edge.target().index(),
+            source_port: edge.weight().output_index(),
+            destination_port: edge.weight().input_index(),
+            kind: edge.weight().kind(),
+            shape: edge.weight().dim().map(<[_]>::to_vec),
+        })
+        .collect();
+    let graph = JsonGraph {
+        version: VERSION,
+        nodes,
+        edges,
+    };
+    let mut json = serde_json::to_string_pretty(&graph).expect("graph is always valid JSON");
+    json.push('\n');
+    json
+}
+
+/// Reads a graph back in from JSON rendered by [`render`].
+pub fn parse(json: &str) -> Result<Graph<Node, Edge>, serde_json::Error> {
+    let json: JsonGraph = serde_json::from_str(json)?;
+    if json.version != VERSION {
+        return Err(serde_json::Error::custom(format!(
+            "unsupported schema version {}, expected {}",
+            json.version, VERSION
+        )));
+    }
+    let mut graph = Graph::new();
+    let mut ids = HashMap::new();
+    for node in json.nodes {
+        let mut new = Node::new(PathBuf::from(node.name), node.ty);
+        *new.attributes_mut() = node.attributes;
+        *new.region_mut() = node.region;
+        *new.output_dims_mut() = node.output_shapes;
+        // The parameters of a block are the total from its summary
+        match node.summary {
+            Some(summary) => *new.summary_mut() = summary,
+            None => new.set_parameters(node.parameters),
+        }
+        if ids.insert(node.id, graph.add_node(new)).is_some() {
+            return Err(serde_json::Error::custom(format!(
+                "duplicate node id {}",
+                node.id
+            )));
+        }
+    }
+    let node = |id: usize| -> Result<NodeIndex, serde_json::Error> {
+        ids.get(&id)
+            .copied()
+            .ok_or_else(|| serde_json::Error::custom(format!("edge uses unknown node id {}", id)))
+    };
+    for edge in json.edges {
+        graph.add_edge(
+            node(edge.source)?,
+            node(edge.destination)?,
+            Edge::new(
+                edge.kind,
+                edge.shape,
+                edge.destination_port,
+                edge.source_port,
+            ),
+        );
+    }
+    Ok(graph)
+}
+
+/// Applies the graph options to a graph read back in, collapsing scopes deeper than `max_depth`
+/// and dropping control edges if they're turned off. Functions stay inlined or not as they were
+/// when the graph was exported.
+pub fn apply_options(graph: &Graph<Node, Edge>, options: &GraphOptions) -> Graph<Node, Edge> {
+    let mut builder = GraphBuilder::new(options);
+    for node in graph.node_weights() {
+        builder.add_node(node);
+    }
+    for edge in graph.edge_references() {
+        let (from, to) = (&graph[edge.source()], &graph[edge.target()]);
+        builder.add_edge(
+            (&from.name().to_string_lossy(), from.ty()),
+            (&to.name().to_string_lossy(), to.ty()),
+            edge.weight().clone(),
+        );
+    }
+    builder.build()
+}
+
+/// Loads a graph from a JSON export, applying the graph options to it.
+pub fn load(input: &Path, options: &GraphOptions) -> Result<Graph<Node, Edge>, Error> {
+    let json = fs::read_to_string(input).map_err(|source| Error::Io {
+        path: input.to_path_buf(),
+        source,
+    })?;
+    let graph = parse(&json).map_err(|e| Error::Parse {
+        path: input.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    Ok(apply_options(&graph, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphBuilder, GraphOptions};
+
+    fn edges(graph: &Graph<Node, Edge>) -> Vec<(usize, usize, Edge)> {
+        graph
+            .edge_references()
+            .map(|e| (e.source().index(), e.target().index(), e.weight().clone()))
+            .collect()
+    }
+
+    fn build(options: &GraphOptions) -> Graph<Node, Edge> {
+        let mut builder = GraphBuilder::new(options);
+        let shape = Some(vec![None, Some(3)]);
+        builder.add_edge(
+            ("x", "Placeholder"),
+            ("net/dense/MatMul", "MatMul"),
+            Edge::new(EdgeKind::Data, shape.clone(), Some(0), Some(0)),
+        );
+        builder.add_edge(
+            ("net/dense/w", "Const"),
+            ("net/dense/MatMul", "MatMul"),
+            Edge::new(EdgeKind::Data, shape.clone(), Some(1), Some(0)),
+        );
+        builder.add_edge(
+            ("net/dense/MatMul", "MatMul"),
+            ("loop", "While"),
+            Edge::new(EdgeKind::Data, None, Some(0), Some(0)),
+        );
+        builder.add_edge(
+            ("loop", "While"),
+            ("loop", "While"),
+            Edge::new(EdgeKind::Back, shape, Some(1), Some(1)),
+        );
+        builder.add_edge(
+            ("x", "Placeholder"),
+            ("loop", "While"),
+            Edge::new(EdgeKind::Control, None, None, None),
+        );
+        builder.add_op("x", "Placeholder", 0);
+        builder.add_op("net/dense/MatMul", "MatMul", 0);
+        builder.add_op("net/dense/w", "Const", 12);
+        builder.add_op("net/act/Relu", "Relu", 0);
+        builder.add_op("loop", "While", 0);
+        let mut attributes = BTreeMap::new();
+        attributes.insert("dtype".to_string(), "DT_FLOAT".to_string());
+        builder.set_attributes("x", "Placeholder", &attributes);
+        builder.set_region("loop", "While", vec!["while loop".to_string()]);
+        builder.set_output_dims("net/dense/MatMul", "MatMul", vec![None]);
+        builder.set_output_dims(
+            "loop",
+            "While",
+            vec![Some(vec![]), Some(vec![None, Some(3)])],
+        );
+        builder.build()
+    }
+
+    #[test]
+    fn round_trips() {
+        let options = GraphOptions {
+            max_depth: Some(2),
+            ..GraphOptions::default()
+        };
+        let graph = build(&options);
+
+        let parsed = parse(&render(&graph)).unwrap();
+        assert!(graph.node_weights().eq(parsed.node_weights()));
+        assert_eq!(edges(&graph), edges(&parsed));
+    }
+
+    #[test]
+    fn applies_options_when_loading() {
+        let exported = parse(&render(&build(&GraphOptions {
+            max_depth: Some(2),
+            ..GraphOptions::default()
+        })))
+        .unwrap();
+        let options = GraphOptions {
+            max_depth: Some(1),
+            control_edges: false,
+            ..GraphOptions::default()
+        };
+        let graph = build(&options);
+
+        let loaded = apply_options(&exported, &options);
+        assert!(graph.node_weights().eq(loaded.node_weights()));
+        assert_eq!(edges(&graph), edges(&loaded));
+        assert!(loaded
+            .edge_weights()
+            .all(|edge| edge.kind() != EdgeKind::Control));
+    }
+
+    #[test]
+    fn rejects_unknown_node_ids() {
+        let json = r#"{
+            "version": 1,
+            "nodes": [{"id": 0, "name": "x", "type": "Placeholder"}],
+            "edges": [{"source": 0, "destination": 1, "kind": "data"}]
+        }"#;
+        assert!(parse(json).is_err());
+    }
+}
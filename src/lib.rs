@@ -0,0 +1,36 @@
+//! Turns neural network models into graphs of their ops which can then be rendered.
+//!
+//! Each model format implements [`ModelGraph`], [`generate_graph`] builds a petgraph `Graph` of
+//! [`Node`]s and [`Edge`]s from any of them and [`load`] does both for a model in any format. The
+//! [`dot`] and [`format`](mod@format) modules render that graph, while [`json`] exports it for
+//! other tools and [`html`] for browsing interactively. [`diagram`] renders Mermaid and PlantUML
+//! for docs and [`xml`] exports GraphML and GEXF for graph analysis tools. [`text`] lists the ops
+//! for reading in a terminal and [`summary`] tabulates them like Keras' `model.summary()`.
+pub mod diagram;
+pub mod dot;
+mod error;
+pub mod filter;
+pub mod format;
+mod graph;
+pub mod graph_def;
+pub mod html;
+pub mod json;
+mod load;
+mod model;
+pub mod onnx;
+pub mod pbtxt;
+pub mod summary;
+pub mod text;
+#[cfg(feature = "tensorflow")]
+mod tf;
+pub mod tflite;
+pub mod xml;
+
+pub use crate::error::{Error, InvalidNameError};
+pub use crate::graph::{BlockSummary, Edge, EdgeKind, GraphOptions, Node};
+pub use crate::load::{load, Loaded};
+pub use crate::model::{
+    generate_graph, Branch, Function, ModelFormat, ModelGraph, NameDecoder, Op, Port,
+};
+#[cfg(feature = "tensorflow")]
+pub use crate::tf::load_graph;
@@ -0,0 +1,88 @@
+//! Loads a model in any of the formats the visualiser reads and builds its graph, picking the
+//! front end from the format of the file.
+use crate::error::Error;
+use crate::graph::{Edge, GraphOptions, Node};
+use crate::model::{generate_graph, ModelFormat, ModelGraph, NameDecoder};
+use crate::{graph_def, json, onnx, tflite};
+use petgraph::graph::Graph;
+use std::path::Path;
+
+/// A graph loaded by [`load`].
+#[derive(Clone, Debug)]
+pub struct Loaded {
+    pub graph: Graph<Node, Edge>,
+    /// Problems which didn't stop the model from loading, such as falling back to reading a
+    /// TensorFlow model without the runtime
+    pub warnings: Vec<String>,
+}
+
+/// Reads a TensorFlow model without the runtime.
+fn read_tensorflow(
+    input: &Path,
+    format: ModelFormat,
+    tags: &[String],
+) -> Result<Box<dyn ModelGraph>, Error> {
+    let model = if format == ModelFormat::SavedModel {
+        graph_def::Model::load_saved_model(input, tags)?
+    } else {
+        graph_def::Model::load(input)?
+    };
+    Ok(Box::new(model))
+}
+
+/// Loads a TensorFlow model with the runtime, falling back to reading it directly if it uses ops
+/// the runtime doesn't have registered.
+#[cfg(feature = "tensorflow")]
+fn load_tensorflow(
+    input: &Path,
+    format: ModelFormat,
+    tags: &[String],
+    warnings: &mut Vec<String>,
+) -> Result<Box<dyn ModelGraph>, Error> {
+    match crate::tf::load_graph(input, tags) {
+        Ok(graph) => Ok(Box::new(graph)),
+        // The model can still be drawn without the runtime, just with fewer shapes
+        Err(e @ Error::UnknownOp { .. }) => {
+            warnings.push(format!(
+                "{}\ndrawing the model without TensorFlow, only shapes saved in the file are shown",
+                e
+            ));
+            read_tensorflow(input, format, tags)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(feature = "tensorflow"))]
+fn load_tensorflow(
+    input: &Path,
+    format: ModelFormat,
+    tags: &[String],
+    _warnings: &mut Vec<String>,
+) -> Result<Box<dyn ModelGraph>, Error> {
+    read_tensorflow(input, format, tags)
+}
+
+/// Loads the model at `input` and builds its graph, `tags` pick the meta graph of a SavedModel.
+/// JSON exports are read back in with the options applied to them.
+pub fn load(
+    input: &Path,
+    tags: &[String],
+    names: &mut NameDecoder,
+    options: &GraphOptions,
+) -> Result<Loaded, Error> {
+    let mut warnings = vec![];
+    let model: Box<dyn ModelGraph> = match ModelFormat::detect(input)? {
+        ModelFormat::Json => {
+            return Ok(Loaded {
+                graph: json::load(input, options)?,
+                warnings,
+            })
+        }
+        ModelFormat::Onnx => Box::new(onnx::Model::load(input)?),
+        ModelFormat::TfLite => Box::new(tflite::Model::load(input)?),
+        format => load_tensorflow(input, format, tags, &mut warnings)?,
+    };
+    let graph = generate_graph(model.as_ref(), names, options)?;
+    Ok(Loaded { graph, warnings })
+}
@@ -1,156 +1,154 @@
-use petgraph::dot::Dot;
-use petgraph::graph::{Graph, NodeIndex};
-use std::collections::HashMap;
+use nn_visualiser::filter::PathEnd;
+use nn_visualiser::format::Format;
+use nn_visualiser::summary::TableStyle;
+use nn_visualiser::{
+    diagram, dot, filter, html, json, summary, text, xml, Error, GraphOptions, NameDecoder,
+};
+use regex::Regex;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::PathBuf;
+use std::process;
 use structopt::StructOpt;
-use tensorflow::{Graph as TfGraph, ImportGraphDefOptions, Operation};
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, StructOpt)]
 pub struct Config {
-    /// Input neural network to render
+    /// Input neural network to render, either a frozen GraphDef (binary or text format), a
+    /// SavedModel directory, an ONNX model, a TensorFlow Lite model or a JSON export
     #[structopt(short, long)]
     input: PathBuf,
+    /// Tags of the meta graph to load from a SavedModel
+    #[structopt(long = "tag", default_value = "serve")]
+    tags: Vec<String>,
     /// Save rendered output here
     #[structopt(short, long)]
     output: Option<PathBuf>,
-    /// Maximum depth to recurse into nested blocks
+    /// Output format (dot, svg, png, pdf, json, html, mermaid, plantuml, graphml, gexf, text or
+    /// summary, markdown and csv for a summary table), defaults to the output file extension or
+    /// dot. Svg, png and pdf require graphviz to be installed
+    #[structopt(short, long)]
+    format: Option<Format>,
+    /// Maximum depth to recurse into nested blocks, for html this is how deep the scopes start off
+    /// expanded
     #[structopt(long)]
     max_depth: Option<usize>,
+    /// Draw each name scope as a cluster containing its ops
+    #[structopt(long)]
+    clusters: bool,
+    /// Don't draw control dependencies between ops
+    #[structopt(long)]
+    no_control_edges: bool,
+    /// Draw the body of a function in place of each op calling it, rather than once in its own
+    /// scope
+    #[structopt(long)]
+    inline_functions: bool,
+    /// Escape bytes in op names and types which aren't valid UTF-8 instead of failing
+    #[structopt(long)]
+    lossy_names: bool,
+    /// Only render the ops with this name (or matching this regex) and the ops around them
+    #[structopt(long)]
+    focus: Option<String>,
+    /// Maximum number of hops away from the focused ops to render
+    #[structopt(long, requires = "focus")]
+    radius: Option<usize>,
+    /// Render the ops feeding into the focused ops, if neither this or --downstream are set both
+    /// directions are rendered
+    #[structopt(long, requires = "focus")]
+    upstream: bool,
+    /// Render the ops consuming the outputs of the focused ops
+    #[structopt(long, requires = "focus")]
+    downstream: bool,
+    /// Only render ops on a path starting from this op (name or regex)
+    #[structopt(long)]
+    from: Option<String>,
+    /// Only render ops on a path ending at this op (name or regex)
+    #[structopt(long)]
+    to: Option<String>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct Node {
-    name: PathBuf,
-    ty: String,
+fn op_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
 }
 
-impl Node {
-    fn from_operation(op: &Operation) -> Self {
-        let name = PathBuf::from(op.name().expect("Op name not valid unicode"));
-        let ty = op.op_type().expect("Op type not valid unicode");
-        Self { name, ty }
+fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let format = config
+        .format
+        .or_else(|| config.output.as_deref().and_then(Format::from_path))
+        .unwrap_or(Format::Dot);
+    let options = GraphOptions {
+        // The html viewer collapses scopes itself so it needs every op to be able to expand them
+        max_depth: config.max_depth.filter(|_| format != Format::Html),
+        control_edges: !config.no_control_edges,
+        inline_functions: config.inline_functions,
+    };
+    let mut names = NameDecoder::new(config.lossy_names);
+    let loaded = nn_visualiser::load(&config.input, &config.tags, &mut names, &options)?;
+    for warning in &loaded.warnings {
+        eprintln!("warning: {}", warning);
     }
-
-    fn from_operation_with_depth(op: &Operation, max_depth: usize) -> Self {
-        let mut ret = Self::from_operation(op);
-        ret.limit_depth(max_depth);
-        ret
-    }
-
-    fn limit_depth(&mut self, max_depth: usize) {
-        let depth = self.name.components().count();
-        for _ in max_depth..depth {
-            self.name.pop();
-        }
-        if max_depth < depth {
-            self.ty = "Block".to_string();
-        }
+    let mut graph = loaded.graph;
+    for name in names.escaped() {
+        eprintln!(
+            "warning: escaped invalid UTF-8 in op name or type: {}",
+            name
+        );
     }
-}
-
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct Edge {
-    dim: Vec<Option<usize>>,
-    input_index: Option<usize>,
-    output_index: Option<usize>,
-}
-
-fn add_op_to_graph(
-    input: (&Operation, Option<usize>),
-    output: (&Operation, Option<usize>),
-    graph: &mut Graph<Node, Edge>,
-    nodes: &mut HashMap<Node, NodeIndex>,
-    max_depth: Option<usize>,
-) {
-    let in_node = max_depth.as_ref().map_or_else(
-        || Node::from_operation(&input.0),
-        |index| Node::from_operation_with_depth(&input.0, *index),
-    );
-    let in_idx = *nodes
-        .entry(in_node.clone())
-        .or_insert_with(|| graph.add_node(in_node.clone()));
-    let out_node = max_depth.as_ref().map_or_else(
-        || Node::from_operation(&output.0),
-        |index| Node::from_operation_with_depth(&output.0, *index),
-    );
-    let out_idx = *nodes
-        .entry(out_node.clone())
-        .or_insert_with(|| graph.add_node(out_node.clone()));
-    if in_node != out_node && graph.find_edge(out_idx, in_idx).is_none() {
-        let edge = Edge {
-            dim: vec![],
-            input_index: input.1,
-            output_index: output.1,
-        };
-        graph.add_edge(out_idx, in_idx, edge);
+    if let Some(focus) = &config.focus {
+        let pattern = op_pattern(focus)?;
+        let both = !config.upstream && !config.downstream;
+        graph = filter::focus(
+            &graph,
+            &pattern,
+            config.radius,
+            config.upstream || both,
+            config.downstream || both,
+        )
+        .ok_or_else(|| format!("--focus `{}` doesn't match any ops", focus))?;
     }
-}
-
-fn generate_graph(nn_graph: &TfGraph, max_depth: Option<usize>) -> Graph<Node, Edge> {
-    let mut graph = Graph::<Node, Edge>::new();
-    let mut nodes = HashMap::new();
-    for op in nn_graph.operation_iter() {
-        for i in 0..op.num_inputs() {
-            let (input, idx) = op.input(i);
-            add_op_to_graph(
-                (&op, Some(i)),
-                (&input, Some(idx)),
-                &mut graph,
-                &mut nodes,
-                max_depth.clone(),
-            );
-        }
-        for i in 0..op.num_outputs() {
-            for (output, idx) in op.output_consumers(i).iter() {
-                add_op_to_graph(
-                    (&output, Some(*idx)),
-                    (&op, Some(i)),
-                    &mut graph,
-                    &mut nodes,
-                    max_depth.clone(),
-                );
-            }
-        }
-        for output in op.control_outputs().iter() {
-            add_op_to_graph(
-                (&output, None),
-                (&op, None),
-                &mut graph,
-                &mut nodes,
-                max_depth.clone(),
-            );
-        }
-        for input in op.control_inputs().iter() {
-            add_op_to_graph(
-                (&op, None),
-                (&input, None),
-                &mut graph,
-                &mut nodes,
-                max_depth.clone(),
-            );
-        }
+    if config.from.is_some() || config.to.is_some() {
+        let from = config.from.as_deref().map(op_pattern).transpose()?;
+        let to = config.to.as_deref().map(op_pattern).transpose()?;
+        graph = filter::between(&graph, from.as_ref(), to.as_ref()).map_err(|end| match end {
+            PathEnd::From => format!(
+                "--from `{}` doesn't match any ops",
+                config.from.as_deref().unwrap_or_default()
+            ),
+            PathEnd::To => format!(
+                "--to `{}` doesn't match any ops",
+                config.to.as_deref().unwrap_or_default()
+            ),
+        })?;
     }
-    graph
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::from_args();
-
-    let input = fs::read(&config.input)?;
-
-    let mut graph = TfGraph::new();
-    graph.import_graph_def(&input, &ImportGraphDefOptions::new())?;
-    let graph = generate_graph(&graph, config.max_depth);
-    let dot = Dot::new(&graph);
+    let rendered = match format {
+        Format::Json => json::render(&graph).into_bytes(),
+        Format::Html => html::render(&graph, config.max_depth).into_bytes(),
+        Format::Mermaid => diagram::render_mermaid(&graph).into_bytes(),
+        Format::PlantUml => diagram::render_plantuml(&graph).into_bytes(),
+        Format::GraphMl => xml::render_graphml(&graph).into_bytes(),
+        Format::Gexf => xml::render_gexf(&graph).into_bytes(),
+        Format::Text => text::render(&graph).into_bytes(),
+        Format::Summary => summary::render(&graph, TableStyle::Text).into_bytes(),
+        Format::Markdown => summary::render(&graph, TableStyle::Markdown).into_bytes(),
+        Format::Csv => summary::render(&graph, TableStyle::Csv).into_bytes(),
+        _ if config.clusters => format.render(&dot::render_clusters(&graph))?,
+        _ => format.render(&dot::render(&graph))?,
+    };
 
     if let Some(o) = config.output {
-        let mut file = fs::File::create(o)?;
-        file.write_all(format!("{:?}", dot).as_bytes())?;
+        fs::write(&o, &rendered).map_err(|e| format!("unable to write {}: {}", o.display(), e))?;
     } else {
-        println!("{:?}", dot);
+        io::stdout().write_all(&rendered)?;
     }
 
     Ok(())
 }
+
+fn main() {
+    if let Err(e) = run(Config::from_args()) {
+        eprintln!("error: {}", e);
+        if matches!(e.downcast_ref(), Some(Error::InvalidName(_))) {
+            eprintln!("rerun with --lossy-names to escape the invalid bytes");
+        }
+        process::exit(1);
+    }
+}
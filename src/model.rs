@@ -0,0 +1,960 @@
+use crate::error::{Error, InvalidNameError};
+use crate::graph::{Edge, EdgeKind, GraphBuilder, GraphOptions, Node};
+use crate::pbtxt;
+use petgraph::graph::Graph;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// A data input of an op, referring to one of the outputs of another op.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Port {
+    /// Name of the op producing the tensor
+    pub op: String,
+    /// Which output of the producing op the tensor is, or which tensor of `output_arg` if it's set
+    pub index: usize,
+    /// Output argument of the producing op the tensor is from, ops inside function bodies refer to
+    /// their inputs this way. The position of the tensor among all the op's outputs isn't known
+    /// then
+    pub output_arg: Option<String>,
+    /// Shape of the tensor with `None` for unknown dimensions, or `None` if the rank is unknown
+    pub dim: Option<Vec<Option<usize>>>,
+}
+
+/// An op in a model along with everything it depends on.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Op {
+    pub name: String,
+    pub ty: String,
+    /// Data inputs in the order the op takes them
+    pub inputs: Vec<Port>,
+    /// Names of the ops which have to run before this one
+    pub control_inputs: Vec<String>,
+    /// Shapes of each output with `None` for unknown dimensions, or `None` if the rank is unknown.
+    /// Empty if the model doesn't record them
+    pub output_dims: Vec<Option<Vec<Option<usize>>>>,
+    /// Number of parameters stored in the op, non-zero for variables and constants
+    pub parameters: usize,
+    /// Extra information about the op such as the types or quantisation of its outputs
+    pub attributes: BTreeMap<String, String>,
+    /// Function called by the op, whose body can be inlined in place of the op
+    pub call: Option<String>,
+    /// Functions run by a control flow op, drawn as regions next to the op
+    pub branches: Vec<Branch>,
+}
+
+/// A function run by a control flow op, such as the body of a `While` or a branch of an `If`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Branch {
+    /// What the function is to the op, such as `body` or `then`
+    pub label: String,
+    pub function: String,
+    /// Index of the first input of the op passed to the function, any before it are only used by
+    /// the op itself such as the predicate of an `If`
+    pub first_input: usize,
+    /// Whether the outputs of the function loop back round to be its next inputs
+    pub loops: bool,
+}
+
+/// A function in the model's library. The body is drawn under a scope named after the function
+/// or inlined at each op calling it.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Function {
+    pub name: String,
+    /// Names of the ops in the body standing in for each argument
+    pub inputs: Vec<String>,
+    /// Names of the function's output arguments
+    pub output_args: Vec<String>,
+    /// The tensors in the body returned as each output argument
+    pub outputs: Vec<Port>,
+    /// Ops in the body, including the argument ops, named relative to the function
+    pub ops: Vec<Op>,
+}
+
+/// Escapes any bytes which aren't part of a valid UTF-8 sequence as `\xNN`.
+fn escape_invalid(mut bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                escaped.push_str(valid);
+                return escaped;
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                escaped.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                let invalid = e.error_len().unwrap_or(rest.len());
+                for b in &rest[..invalid] {
+                    escaped.push_str(&format!("\\x{:02x}", b));
+                }
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+}
+
+/// Turns the raw bytes of op names and types into strings. Names which aren't valid UTF-8 are an
+/// error unless the decoder is lossy, then the invalid bytes are escaped and the name is recorded
+/// so it can be reported.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NameDecoder {
+    lossy: bool,
+    escaped: BTreeSet<String>,
+}
+
+impl NameDecoder {
+    pub fn new(lossy: bool) -> Self {
+        Self {
+            lossy,
+            escaped: BTreeSet::new(),
+        }
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<String, InvalidNameError> {
+        if let Ok(name) = std::str::from_utf8(bytes) {
+            return Ok(name.to_string());
+        }
+        let name = escape_invalid(bytes);
+        if self.lossy {
+            self.escaped.insert(name.clone());
+            Ok(name)
+        } else {
+            Err(InvalidNameError { name })
+        }
+    }
+
+    /// Names which had invalid bytes escaped
+    pub fn escaped(&self) -> &BTreeSet<String> {
+        &self.escaped
+    }
+}
+
+/// A source of ops for the graph builder, each model format the visualiser reads implements this.
+pub trait ModelGraph {
+    /// Every op in the model, with names and types decoded by `names`
+    fn ops(&self, names: &mut NameDecoder) -> Result<Vec<Op>, InvalidNameError>;
+
+    /// Functions which ops in the model can call, most formats don't have any
+    fn functions(&self, _names: &mut NameDecoder) -> Result<Vec<Function>, InvalidNameError> {
+        Ok(vec![])
+    }
+
+    /// Scopes to draw as labelled regions, along with their labels. Ops under a scope are in its
+    /// region.
+    fn regions(&self, _names: &mut NameDecoder) -> Result<Vec<(String, String)>, InvalidNameError> {
+        Ok(vec![])
+    }
+}
+
+/// The model formats the visualiser can read.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ModelFormat {
+    /// A binary tensorflow GraphDef protobuf
+    GraphDef,
+    /// A text format tensorflow GraphDef protobuf
+    GraphDefText,
+    /// A tensorflow SavedModel directory
+    SavedModel,
+    /// An ONNX protobuf
+    Onnx,
+    /// A TensorFlow Lite flatbuffer
+    TfLite,
+    /// A graph previously exported as JSON
+    Json,
+}
+
+impl ModelFormat {
+    /// Works out the format of a model from the file extension, falling back to looking at the
+    /// start of the file if the extension isn't recognised.
+    pub fn detect(path: &Path) -> Result<Self, Error> {
+        let unsupported = |reason: &str| Error::UnsupportedFormat {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        };
+        if path.is_dir() {
+            let has_graph = ["saved_model.pb", "saved_model.pbtxt"]
+                .iter()
+                .any(|f| path.join(f).is_file());
+            return if has_graph {
+                Ok(ModelFormat::SavedModel)
+            } else {
+                Err(unsupported("directory has no saved_model.pb"))
+            };
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("onnx") => return Ok(ModelFormat::Onnx),
+            Some("pb") => return Ok(ModelFormat::GraphDef),
+            Some("pbtxt" | "pbtext") => return Ok(ModelFormat::GraphDefText),
+            Some("tflite") => return Ok(ModelFormat::TfLite),
+            Some("json") => return Ok(ModelFormat::Json),
+            _ => {}
+        }
+        let mut magic = Vec::with_capacity(512);
+        File::open(path)
+            .and_then(|f| f.take(512).read_to_end(&mut magic))
+            .map_err(|source| Error::Io {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        // TFLite has a file identifier after the root table offset. ONNX models start with the
+        // `ir_version` varint (field 1) while a GraphDef starts with one of its fields, normally
+        // the repeated `node` (field 1, length delimited)
+        if magic.get(4..8) == Some(b"TFL3") {
+            Ok(ModelFormat::TfLite)
+        } else if magic.first() == Some(&0x08) {
+            Ok(ModelFormat::Onnx)
+        } else if magic.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+            Ok(ModelFormat::Json)
+        } else if pbtxt::is_text(&magic) {
+            Ok(ModelFormat::GraphDefText)
+        } else if matches!(magic.first(), Some(0x0a | 0x12 | 0x18 | 0x22)) {
+            Ok(ModelFormat::GraphDef)
+        } else if magic.is_empty() {
+            Err(unsupported("the file is empty"))
+        } else {
+            Err(unsupported("the start of the file isn't recognised"))
+        }
+    }
+}
+
+/// Control flow regions found in the model.
+#[derive(Default)]
+struct Regions {
+    /// Scopes drawn as regions, such as the bodies of branches, along with the label of the
+    /// region
+    scopes: Vec<(String, String)>,
+    /// Edges taking values from the end of a loop back to its start, as producer and consumer
+    back_edges: HashSet<(String, String)>,
+}
+
+/// Adds the bodies of the branches of control flow ops to the graph, each scoped under the op and
+/// the branch label. The arguments of a branch are fed by the op's inputs and its outputs go back
+/// into the op, for loops these are back edges.
+fn expand_branches(
+    ops: Vec<Op>,
+    functions: &HashMap<&str, &Function>,
+    regions: &mut Regions,
+    stack: &mut Vec<String>,
+) -> Vec<Op> {
+    let mut expanded = vec![];
+    for mut op in ops {
+        let mut bodies = vec![];
+        let mut outputs = vec![];
+        for branch in &op.branches {
+            let function = match functions.get(branch.function.as_str()) {
+                Some(function) if !stack.contains(&function.name) => *function,
+                _ => continue,
+            };
+            let scope = format!("{}/{}", op.name, branch.label);
+            regions
+                .scopes
+                .push((scope.clone(), format!("{} {}", branch.label, op.name)));
+            let scoped = |name: &str| format!("{}/{}", scope, name);
+            let body = function
+                .ops
+                .iter()
+                .map(|body_op| {
+                    let arg = function.inputs.iter().position(|a| *a == body_op.name);
+                    let inputs = match arg {
+                        Some(arg) => op
+                            .inputs
+                            .get(branch.first_input + arg)
+                            .cloned()
+                            .into_iter()
+                            .collect(),
+                        None => body_op
+                            .inputs
+                            .iter()
+                            .map(|port| Port {
+                                op: scoped(&port.op),
+                                ..port.clone()
+                            })
+                            .collect(),
+                    };
+                    Op {
+                        name: scoped(&body_op.name),
+                        inputs,
+                        control_inputs: body_op.control_inputs.iter().map(|i| scoped(i)).collect(),
+                        ..body_op.clone()
+                    }
+                })
+                .collect();
+            stack.push(function.name.clone());
+            bodies.extend(expand_branches(body, functions, regions, stack));
+            stack.pop();
+            for output in &function.outputs {
+                let port = Port {
+                    op: scoped(&output.op),
+                    ..output.clone()
+                };
+                if branch.loops {
+                    regions
+                        .back_edges
+                        .insert((port.op.clone(), op.name.clone()));
+                }
+                outputs.push(port);
+            }
+        }
+        op.inputs.extend(outputs);
+        expanded.push(op);
+        expanded.extend(bodies);
+    }
+    expanded
+}
+
+/// The op whose control flow region an op is in, along with which of its outputs is used. That's
+/// the first input, or first control input if it has no data inputs.
+fn region_parent<'a>(op: &'a Op, ops: &HashMap<&str, &Op>) -> Option<(&'a str, usize)> {
+    op.inputs
+        .first()
+        .map(|port| {
+            // `Switch` ops have the output arguments `output_false` and `output_true`
+            let index = match port.output_arg.as_deref() {
+                Some(arg) => usize::from(arg == "output_true"),
+                None => port.index,
+            };
+            (port.op.as_str(), index)
+        })
+        .or_else(|| op.control_inputs.first().map(|input| (input.as_str(), 0)))
+        .filter(|(name, _)| ops.contains_key(name))
+}
+
+/// Works out which TF1 control flow regions each op is in, outermost first. Loops are entered
+/// through `Enter` ops and left through `Exit` ops. The outputs of a `Switch` which isn't part of
+/// a loop start the branches of a conditional, which end at a `Merge`.
+fn control_flow_regions(ops: &[Op]) -> HashMap<&str, Vec<String>> {
+    let by_name = ops
+        .iter()
+        .map(|op| (op.name.as_str(), op))
+        .collect::<HashMap<_, _>>();
+    let ty = |name: &str| by_name.get(name).map_or("", |op| op.ty.as_str());
+
+    let mut regions = HashMap::<&str, Vec<String>>::new();
+    for op in ops {
+        // Walk up to an op whose region is known then work back down
+        let mut chain = vec![];
+        let mut visited = HashSet::new();
+        let mut current = Some(op.name.as_str());
+        while let Some(name) = current {
+            if regions.contains_key(name) || !visited.insert(name) {
+                break;
+            }
+            chain.push(by_name[name]);
+            current = region_parent(by_name[name], &by_name).map(|(name, _)| name);
+        }
+        for op in chain.into_iter().rev() {
+            let (mut region, parent) = match region_parent(op, &by_name) {
+                Some((name, index)) => (
+                    regions.get(name).cloned().unwrap_or_default(),
+                    Some((by_name[name], index)),
+                ),
+                None => (vec![], None),
+            };
+            if let Some((switch, index)) =
+                parent.filter(|(p, _)| matches!(p.ty.as_str(), "Switch" | "RefSwitch"))
+            {
+                let predicate = switch.inputs.get(1).map_or("", |p| p.op.as_str());
+                if ty(predicate) != "LoopCond" {
+                    let branch = if index == 1 { "true" } else { "false" };
+                    region.push(format!("if {} {}", predicate, branch));
+                }
+            }
+            match op.ty.as_str() {
+                "Enter" | "RefEnter" => {
+                    let frame = op.attributes.get("frame_name").unwrap_or(&op.name);
+                    region.push(format!("while {}", frame));
+                }
+                "Exit" | "RefExit" => {
+                    region.pop();
+                }
+                "Merge" | "RefMerge" => {
+                    let loops = op
+                        .inputs
+                        .iter()
+                        .any(|p| matches!(ty(&p.op), "NextIteration" | "RefNextIteration"));
+                    if !loops && region.last().is_some_and(|r| r.starts_with("if ")) {
+                        region.pop();
+                    }
+                }
+                _ => {}
+            }
+            regions.insert(op.name.as_str(), region);
+        }
+    }
+    regions.retain(|_, region| !region.is_empty());
+    regions
+}
+
+/// An output of an op as its name, output argument and index.
+type OutputKey = (String, Option<String>, usize);
+
+/// Replaces every op calling a function with a copy of the function body scoped under the op's
+/// name. Calls inside the body are inlined too, unless the function is recursive. The outputs of
+/// inlined calls are recorded in `outputs` so the ops consuming them can be rewired, by position
+/// and by output argument.
+fn inline_calls(
+    ops: Vec<Op>,
+    functions: &HashMap<&str, &Function>,
+    outputs: &mut HashMap<OutputKey, Port>,
+    stack: &mut Vec<String>,
+) -> Vec<Op> {
+    let mut inlined = vec![];
+    for op in ops {
+        let function = match op.call.as_deref().and_then(|f| functions.get(f)) {
+            Some(function) if !stack.contains(&function.name) => *function,
+            _ => {
+                inlined.push(op);
+                continue;
+            }
+        };
+        // Reading an argument reads the matching input of the call instead
+        let args = function
+            .inputs
+            .iter()
+            .map(String::as_str)
+            .zip(&op.inputs)
+            .collect::<HashMap<_, _>>();
+        let scoped = |port: &Port| match args.get(port.op.as_str()) {
+            Some(arg) if arg.dim.is_none() => Port {
+                dim: port.dim.clone(),
+                ..(*arg).clone()
+            },
+            Some(arg) => (*arg).clone(),
+            None => Port {
+                op: format!("{}/{}", op.name, port.op),
+                ..port.clone()
+            },
+        };
+        for (i, port) in function.outputs.iter().enumerate() {
+            outputs.insert((op.name.clone(), None, i), scoped(port));
+            if let Some(arg) = function.output_args.get(i) {
+                outputs.insert((op.name.clone(), Some(arg.clone()), 0), scoped(port));
+            }
+        }
+        let body = function
+            .ops
+            .iter()
+            .filter(|body_op| !function.inputs.contains(&body_op.name))
+            .map(|body_op| Op {
+                name: format!("{}/{}", op.name, body_op.name),
+                inputs: body_op.inputs.iter().map(scoped).collect(),
+                control_inputs: body_op
+                    .control_inputs
+                    .iter()
+                    .map(|input| format!("{}/{}", op.name, input))
+                    .collect(),
+                ..body_op.clone()
+            })
+            .collect();
+        stack.push(function.name.clone());
+        inlined.extend(inline_calls(body, functions, outputs, stack));
+        stack.pop();
+    }
+    inlined
+}
+
+/// Adds the bodies of the functions to the ops, either once each under a scope named after the
+/// function or inlined in place of every op calling them.
+fn add_functions(mut ops: Vec<Op>, functions: &[&Function], inline: bool) -> Vec<Op> {
+    if !inline {
+        for function in functions {
+            let scoped = |name: &str| format!("{}/{}", function.name, name);
+            ops.extend(function.ops.iter().map(|op| {
+                Op {
+                    name: scoped(&op.name),
+                    inputs: op
+                        .inputs
+                        .iter()
+                        .map(|port| Port {
+                            op: scoped(&port.op),
+                            ..port.clone()
+                        })
+                        .collect(),
+                    control_inputs: op.control_inputs.iter().map(|i| scoped(i)).collect(),
+                    ..op.clone()
+                }
+            }));
+        }
+        return ops;
+    }
+    let functions = functions
+        .iter()
+        .map(|f| (f.name.as_str(), *f))
+        .collect::<HashMap<_, _>>();
+    let mut outputs = HashMap::new();
+    let mut ops = inline_calls(ops, &functions, &mut outputs, &mut vec![]);
+    // An output of an inlined call can itself be the output of a call inlined inside it, so keep
+    // following them until reaching an op which is still in the graph
+    for op in &mut ops {
+        for port in &mut op.inputs {
+            for _ in 0..=outputs.len() {
+                match outputs.get(&(port.op.clone(), port.output_arg.clone(), port.index)) {
+                    Some(output) => {
+                        let dim = port.dim.take();
+                        *port = output.clone();
+                        if dim.is_some() {
+                            port.dim = dim;
+                        }
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    ops
+}
+
+/// Builds a graph of the ops in a model.
+pub fn generate_graph<M>(
+    model: &M,
+    names: &mut NameDecoder,
+    options: &GraphOptions,
+) -> Result<Graph<Node, Edge>, InvalidNameError>
+where
+    M: ModelGraph + ?Sized,
+{
+    let functions = model.functions(names)?;
+    let function_names = functions
+        .iter()
+        .map(|f| (f.name.as_str(), f))
+        .collect::<HashMap<_, _>>();
+    let mut regions = Regions {
+        scopes: model.regions(names)?,
+        ..Regions::default()
+    };
+    let ops = model.ops(names)?;
+    // Functions used as branches are drawn next to the ops running them instead
+    let branches = ops
+        .iter()
+        .chain(functions.iter().flat_map(|f| &f.ops))
+        .flat_map(|op| &op.branches)
+        .map(|branch| branch.function.as_str())
+        .collect::<HashSet<_>>();
+    let called = functions
+        .iter()
+        .filter(|f| !branches.contains(f.name.as_str()))
+        .collect::<Vec<_>>();
+    // Bodies which aren't inlined are drawn once each in their own region, with an edge from
+    // every op calling the function to the first op of its body
+    let mut entries = HashMap::new();
+    if !options.inline_functions {
+        for function in &called {
+            regions
+                .scopes
+                .push((function.name.clone(), format!("function {}", function.name)));
+            if let Some(first) = function.ops.first() {
+                entries.insert(
+                    function.name.as_str(),
+                    format!("{}/{}", function.name, first.name),
+                );
+            }
+        }
+    }
+    let ops = add_functions(ops, &called, options.inline_functions);
+    // Branches are expanded once the function bodies are in place, so control flow inside a
+    // function is expanded too
+    let ops = expand_branches(ops, &function_names, &mut regions, &mut vec![]);
+
+    let types = ops
+        .iter()
+        .map(|op| (op.name.as_str(), op.ty.as_str()))
+        .collect::<HashMap<_, _>>();
+    let mut builder = GraphBuilder::new(options);
+    for op in &ops {
+        for (i, port) in op.inputs.iter().enumerate() {
+            if let Some(ty) = types.get(port.op.as_str()) {
+                let back_edge = matches!(*ty, "NextIteration" | "RefNextIteration")
+                    || regions
+                        .back_edges
+                        .contains(&(port.op.clone(), op.name.clone()));
+                let kind = if back_edge {
+                    EdgeKind::Back
+                } else {
+                    EdgeKind::Data
+                };
+                // The position of an output argument's tensors among the op's outputs isn't known
+                let index = Some(port.index).filter(|_| port.output_arg.is_none());
+                let edge = Edge::new(kind, port.dim.clone(), Some(i), index);
+                builder.add_edge((&port.op, ty), (&op.name, &op.ty), edge);
+            }
+        }
+        for input in &op.control_inputs {
+            if let Some(ty) = types.get(input.as_str()) {
+                let edge = Edge::new(EdgeKind::Control, None, None, None);
+                builder.add_edge((input, ty), (&op.name, &op.ty), edge);
+            }
+        }
+        if let Some(entry) = op.call.as_deref().and_then(|f| entries.get(f)) {
+            if let Some(ty) = types.get(entry.as_str()) {
+                let edge = Edge::new(EdgeKind::Call, None, None, None);
+                builder.add_edge((&op.name, &op.ty), (entry, ty), edge);
+            }
+        }
+    }
+    let mut control_flow = control_flow_regions(&ops);
+    for op in &ops {
+        builder.add_op(&op.name, &op.ty, op.parameters);
+        if !op.attributes.is_empty() {
+            builder.set_attributes(&op.name, &op.ty, &op.attributes);
+        }
+        if !op.output_dims.is_empty() {
+            builder.set_output_dims(&op.name, &op.ty, op.output_dims.clone());
+        }
+        // Ops are in the regions of every scope they're inside, such as branch bodies, and TF1
+        // regions are nested inside those
+        let mut region = regions
+            .scopes
+            .iter()
+            .filter(|(scope, _)| op.name.starts_with(&format!("{}/", scope)))
+            .map(|(_, label)| label.clone())
+            .collect::<Vec<_>>();
+        region.extend(control_flow.remove(op.name.as_str()).unwrap_or_default());
+        if !region.is_empty() {
+            builder.set_region(&op.name, &op.ty, region);
+        }
+    }
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_def;
+    use petgraph::visit::EdgeRef;
+
+    fn graph(text: &str, options: &GraphOptions) -> Graph<Node, Edge> {
+        let bytes = pbtxt::graph_def_to_binary(text).unwrap();
+        let model = graph_def::Model::decode(&bytes).unwrap();
+        generate_graph(&model, &mut NameDecoder::new(false), options).unwrap()
+    }
+
+    fn region<'a>(graph: &'a Graph<Node, Edge>, name: &str) -> &'a [String] {
+        graph
+            .node_weights()
+            .find(|node| node.name() == Path::new(name))
+            .unwrap_or_else(|| panic!("no node named {}", name))
+            .region()
+    }
+
+    fn edges(graph: &Graph<Node, Edge>, kind: EdgeKind) -> Vec<(String, String)> {
+        let mut edges = graph
+            .edge_references()
+            .filter(|e| e.weight().kind() == kind)
+            .map(|e| {
+                (
+                    graph[e.source()].name().display().to_string(),
+                    graph[e.target()].name().display().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+        edges
+    }
+
+    const LOOP_IN_FUNCTION: &str = r#"
+        node { name: "x" op: "Placeholder" }
+        node { name: "call" op: "StatefulPartitionedCall" input: "x"
+          attr { key: "f" value { func { name: "model_fn" } } } }
+        library {
+          function { signature { name: "model_fn" input_arg { name: "a" } output_arg { name: "r" } }
+            node_def { name: "loop" op: "While" input: "a"
+              attr { key: "cond" value { func { name: "cond_fn" } } }
+              attr { key: "body" value { func { name: "body_fn" } } } }
+            ret { key: "r" value: "loop:output:0" } }
+          function { signature { name: "cond_fn" input_arg { name: "i" } output_arg { name: "r" } }
+            node_def { name: "less" op: "Less" input: "i" }
+            ret { key: "r" value: "less:z:0" } }
+          function { signature { name: "body_fn" input_arg { name: "i" } output_arg { name: "r" } }
+            node_def { name: "add" op: "AddV2" input: "i" }
+            ret { key: "r" value: "add:z:0" } }
+        }
+    "#;
+
+    #[test]
+    fn expands_branches_inside_functions() {
+        let graph = graph(LOOP_IN_FUNCTION, &GraphOptions::default());
+        assert_eq!(
+            region(&graph, "model_fn/loop/body/add"),
+            ["function model_fn", "body model_fn/loop"]
+        );
+        assert_eq!(
+            region(&graph, "model_fn/loop/cond/less"),
+            ["function model_fn", "cond model_fn/loop"]
+        );
+        assert_eq!(
+            edges(&graph, EdgeKind::Back),
+            [(
+                "model_fn/loop/body/add".to_string(),
+                "model_fn/loop".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn expands_branches_inside_inlined_functions() {
+        let options = GraphOptions {
+            inline_functions: true,
+            ..GraphOptions::default()
+        };
+        let graph = graph(LOOP_IN_FUNCTION, &options);
+        assert_eq!(region(&graph, "call/loop/body/add"), ["body call/loop"]);
+        assert_eq!(
+            edges(&graph, EdgeKind::Back),
+            [("call/loop/body/add".to_string(), "call/loop".to_string())]
+        );
+    }
+
+    #[test]
+    fn finds_tf1_while_frames() {
+        let graph = graph(
+            r#"
+            node { name: "x" op: "Placeholder" }
+            node { name: "while/Enter" op: "Enter" input: "x"
+              attr { key: "frame_name" value { s: "while/loop" } } }
+            node { name: "while/Merge" op: "Merge" input: "while/Enter" input: "while/NextIteration" }
+            node { name: "while/Less" op: "Less" input: "while/Merge" }
+            node { name: "while/LoopCond" op: "LoopCond" input: "while/Less" }
+            node { name: "while/Switch" op: "Switch" input: "while/Merge" input: "while/LoopCond" }
+            node { name: "while/Identity" op: "Identity" input: "while/Switch:1" }
+            node { name: "while/add" op: "Add" input: "while/Identity" }
+            node { name: "while/NextIteration" op: "NextIteration" input: "while/add" }
+            node { name: "while/Exit" op: "Exit" input: "while/Switch" }
+            node { name: "out" op: "Identity" input: "while/Exit" }
+            "#,
+            &GraphOptions::default(),
+        );
+        for name in [
+            "while/Enter",
+            "while/Merge",
+            "while/Switch",
+            "while/NextIteration",
+        ] {
+            assert_eq!(region(&graph, name), ["while while/loop"], "{}", name);
+        }
+        // The switch's true output stays in the loop rather than starting a conditional
+        assert_eq!(region(&graph, "while/add"), ["while while/loop"]);
+        assert!(region(&graph, "x").is_empty());
+        assert!(region(&graph, "while/Exit").is_empty());
+        assert!(region(&graph, "out").is_empty());
+        assert_eq!(
+            edges(&graph, EdgeKind::Back),
+            [("while/NextIteration".to_string(), "while/Merge".to_string())]
+        );
+    }
+
+    #[test]
+    fn finds_tf1_cond_branches() {
+        let graph = graph(
+            r#"
+            node { name: "x" op: "Placeholder" }
+            node { name: "p" op: "Placeholder" }
+            node { name: "cond/Switch" op: "Switch" input: "x" input: "p" }
+            node { name: "cond/a" op: "Neg" input: "cond/Switch:1" }
+            node { name: "cond/a2" op: "Relu" input: "cond/a" }
+            node { name: "cond/b" op: "Abs" input: "cond/Switch" }
+            node { name: "cond/Merge" op: "Merge" input: "cond/b" input: "cond/a2" }
+            node { name: "out" op: "Identity" input: "cond/Merge" }
+            "#,
+            &GraphOptions::default(),
+        );
+        assert_eq!(region(&graph, "cond/a"), ["if p true"]);
+        assert_eq!(region(&graph, "cond/a2"), ["if p true"]);
+        assert_eq!(region(&graph, "cond/b"), ["if p false"]);
+        assert!(region(&graph, "cond/Switch").is_empty());
+        assert!(region(&graph, "cond/Merge").is_empty());
+        assert!(region(&graph, "out").is_empty());
+        assert!(edges(&graph, EdgeKind::Back).is_empty());
+    }
+
+    #[test]
+    fn expands_tf2_while_and_if() {
+        let graph = graph(
+            r#"
+            node { name: "x" op: "Placeholder" }
+            node { name: "p" op: "Placeholder" }
+            node { name: "loop" op: "StatelessWhile" input: "x"
+              attr { key: "cond" value { func { name: "cond_fn" } } }
+              attr { key: "body" value { func { name: "body_fn" } } } }
+            node { name: "branch" op: "StatelessIf" input: "p" input: "loop"
+              attr { key: "then_branch" value { func { name: "then_fn" } } }
+              attr { key: "else_branch" value { func { name: "else_fn" } } } }
+            library {
+              function { signature { name: "cond_fn" input_arg { name: "i" } output_arg { name: "r" } }
+                node_def { name: "less" op: "Less" input: "i" }
+                ret { key: "r" value: "less:z:0" } }
+              function { signature { name: "body_fn" input_arg { name: "i" } output_arg { name: "r" } }
+                node_def { name: "add" op: "AddV2" input: "i" }
+                ret { key: "r" value: "add:z:0" } }
+              function { signature { name: "then_fn" input_arg { name: "v" } output_arg { name: "r" } }
+                node_def { name: "neg" op: "Neg" input: "v" }
+                ret { key: "r" value: "neg:y:0" } }
+              function { signature { name: "else_fn" input_arg { name: "v" } output_arg { name: "r" } }
+                node_def { name: "abs" op: "Abs" input: "v" }
+                ret { key: "r" value: "abs:y:0" } }
+            }
+            "#,
+            &GraphOptions::default(),
+        );
+        assert_eq!(region(&graph, "loop/cond/less"), ["cond loop"]);
+        assert_eq!(region(&graph, "loop/body/add"), ["body loop"]);
+        assert_eq!(region(&graph, "branch/then/neg"), ["then branch"]);
+        assert_eq!(region(&graph, "branch/else/abs"), ["else branch"]);
+        assert!(region(&graph, "loop").is_empty());
+        // Branch functions aren't drawn again as functions of their own
+        assert!(graph
+            .node_weights()
+            .all(|node| !node.name().starts_with("body_fn")));
+        // Only the loop body feeds back into the op, the branches of the `If` don't loop
+        assert_eq!(
+            edges(&graph, EdgeKind::Back),
+            [("loop/body/add".to_string(), "loop".to_string())]
+        );
+        let data = edges(&graph, EdgeKind::Data);
+        for (from, to) in [
+            ("x", "loop/body/i"),
+            ("loop", "branch/then/v"),
+            ("loop", "branch/else/v"),
+            ("branch/then/neg", "branch"),
+            ("branch/else/abs", "branch"),
+        ] {
+            assert!(
+                data.contains(&(from.to_string(), to.to_string())),
+                "{} -> {}",
+                from,
+                to
+            );
+        }
+        // The predicate is only used by the `If` itself
+        assert!(!data.contains(&("p".to_string(), "branch/then/v".to_string())));
+    }
+
+    fn inlined() -> GraphOptions {
+        GraphOptions {
+            inline_functions: true,
+            ..GraphOptions::default()
+        }
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    }
+
+    const NESTED_CALLS: &str = r#"
+        node { name: "x" op: "Placeholder" }
+        node { name: "call" op: "StatefulPartitionedCall" input: "x"
+          attr { key: "f" value { func { name: "outer" } } } }
+        node { name: "out" op: "Identity" input: "call:1" }
+        library {
+          function {
+            signature { name: "outer" input_arg { name: "a" } output_arg { name: "r0" }
+              output_arg { name: "r1" } }
+            node_def { name: "relu" op: "Relu" input: "a" }
+            node_def { name: "inner_call" op: "inner" input: "relu:activations:0" }
+            ret { key: "r0" value: "relu:activations:0" }
+            ret { key: "r1" value: "inner_call:y:0" }
+          }
+          function { signature { name: "inner" input_arg { name: "p" } output_arg { name: "y" } }
+            node_def { name: "sq" op: "Square" input: "p" }
+            ret { key: "y" value: "sq:y:0" } }
+        }
+    "#;
+
+    #[test]
+    fn draws_each_function_once() {
+        let graph = graph(NESTED_CALLS, &GraphOptions::default());
+        assert_eq!(
+            edges(&graph, EdgeKind::Call),
+            pairs(&[("call", "outer/a"), ("outer/inner_call", "inner/p")])
+        );
+        assert_eq!(
+            edges(&graph, EdgeKind::Data),
+            pairs(&[
+                ("call", "out"),
+                ("inner/p", "inner/sq"),
+                ("outer/a", "outer/relu"),
+                ("outer/relu", "outer/inner_call"),
+                ("x", "call"),
+            ])
+        );
+        assert_eq!(region(&graph, "inner/sq"), ["function inner"]);
+        // Only the position of `relu`'s tensor within its output argument is known
+        let edge = graph
+            .edge_references()
+            .find(|e| graph[e.target()].name() == Path::new("outer/inner_call"))
+            .unwrap();
+        assert_eq!(edge.weight().output_index(), None);
+    }
+
+    #[test]
+    fn inlines_nested_calls() {
+        let graph = graph(NESTED_CALLS, &inlined());
+        assert_eq!(
+            edges(&graph, EdgeKind::Data),
+            pairs(&[
+                ("call/inner_call/sq", "out"),
+                ("call/relu", "call/inner_call/sq"),
+                ("x", "call/relu"),
+            ])
+        );
+        assert!(edges(&graph, EdgeKind::Call).is_empty());
+    }
+
+    #[test]
+    fn inlines_identity_returns() {
+        let graph = graph(
+            r#"
+            node { name: "x" op: "Placeholder"
+              attr { key: "_output_shapes" value { list { shape { dim { size: 2 } } } } } }
+            node { name: "call" op: "id_fn" input: "x" }
+            node { name: "out" op: "Identity" input: "call" }
+            library {
+              function { signature { name: "id_fn" input_arg { name: "a" } output_arg { name: "r" } }
+                ret { key: "r" value: "a" } }
+            }
+            "#,
+            &inlined(),
+        );
+        assert_eq!(edges(&graph, EdgeKind::Data), pairs(&[("x", "out")]));
+        let edge = graph.edge_weights().next().unwrap();
+        assert_eq!(edge.dim(), Some(&[Some(2)][..]));
+        assert_eq!(edge.output_index(), Some(0));
+    }
+
+    const RECURSIVE_CALL: &str = r#"
+        node { name: "x" op: "Placeholder" }
+        node { name: "call" op: "rec" input: "x" }
+        node { name: "out" op: "Identity" input: "call" }
+        library {
+          function { signature { name: "rec" input_arg { name: "a" } output_arg { name: "r" } }
+            node_def { name: "step" op: "Neg" input: "a" }
+            node_def { name: "again" op: "rec" input: "step:y:0" }
+            ret { key: "r" value: "again:r:0" } }
+        }
+    "#;
+
+    #[test]
+    fn stops_inlining_recursive_calls() {
+        let graph = graph(RECURSIVE_CALL, &inlined());
+        assert_eq!(
+            edges(&graph, EdgeKind::Data),
+            pairs(&[
+                ("call/again", "out"),
+                ("call/step", "call/again"),
+                ("x", "call/step"),
+            ])
+        );
+    }
+
+    #[test]
+    fn calls_recursive_functions_from_their_own_body() {
+        let graph = graph(RECURSIVE_CALL, &GraphOptions::default());
+        assert_eq!(
+            edges(&graph, EdgeKind::Call),
+            pairs(&[("call", "rec/a"), ("rec/again", "rec/a")])
+        );
+    }
+}
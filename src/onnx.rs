@@ -0,0 +1,343 @@
+//! Front end for ONNX models, the protobuf is decoded directly so there's no dependency on the
+//! ONNX runtime or python.
+use crate::error::{Error, InvalidNameError};
+use crate::model::{ModelGraph, NameDecoder, Op, Port};
+use prost::Message;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+/// A decoded ONNX model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Model(proto::ModelProto);
+
+impl Model {
+    pub fn decode(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
+        proto::ModelProto::decode(bytes).map(Model)
+    }
+
+    pub fn load(input: &Path) -> Result<Self, Error> {
+        let bytes = fs::read(input).map_err(|source| Error::Io {
+            path: input.to_path_buf(),
+            source,
+        })?;
+        Self::decode(&bytes).map_err(|e| Error::Parse {
+            path: input.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+}
+
+fn value_info_dims(info: &proto::ValueInfoProto) -> Option<Vec<Option<usize>>> {
+    let shape = info.r#type.as_ref()?.tensor_type.as_ref()?.shape.as_ref()?;
+    let dims = shape
+        .dim
+        .iter()
+        .map(|d| d.dim_value.and_then(|v| usize::try_from(v).ok()))
+        .collect();
+    Some(dims)
+}
+
+/// ONNX node names are optional and frequently start with a `/`, so fall back to the name of the
+/// first output and strip the leading slash so the scopes line up with tensorflow models.
+fn node_name(node: &proto::NodeProto) -> &[u8] {
+    let mut name = if node.name.is_empty() {
+        node.output.first().map(Vec::as_slice).unwrap_or_default()
+    } else {
+        node.name.as_slice()
+    };
+    while let Some(rest) = name.strip_prefix(b"/") {
+        name = rest;
+    }
+    name
+}
+
+fn tensor_dims(tensor: &proto::TensorProto) -> Vec<Option<usize>> {
+    tensor
+        .dims
+        .iter()
+        .map(|d| usize::try_from(*d).ok())
+        .collect()
+}
+
+/// Graph inputs and initializers become ops of type `Input` and `Initializer` so the tensors
+/// feeding the network are visible.
+impl ModelGraph for Model {
+    fn ops(&self, names: &mut NameDecoder) -> Result<Vec<Op>, InvalidNameError> {
+        let graph = match self.0.graph.as_ref() {
+            Some(graph) => graph,
+            None => return Ok(vec![]),
+        };
+
+        let mut shapes = HashMap::new();
+        for info in graph
+            .input
+            .iter()
+            .chain(graph.output.iter())
+            .chain(graph.value_info.iter())
+        {
+            shapes.insert(info.name.as_slice(), value_info_dims(info));
+        }
+        for init in &graph.initializer {
+            shapes.insert(init.name.as_slice(), Some(tensor_dims(init)));
+        }
+
+        let mut ops = vec![];
+        // Map every tensor to the op producing it and which output it is
+        let mut producers = HashMap::new();
+        for init in &graph.initializer {
+            producers.insert(init.name.as_slice(), (init.name.as_slice(), 0));
+            ops.push(Op {
+                name: names.decode(&init.name)?,
+                ty: "Initializer".to_string(),
+                inputs: vec![],
+                control_inputs: vec![],
+                output_dims: vec![Some(tensor_dims(init))],
+                parameters: tensor_dims(init)
+                    .into_iter()
+                    .product::<Option<usize>>()
+                    .unwrap_or(0),
+                attributes: BTreeMap::new(),
+                call: None,
+                branches: vec![],
+            });
+        }
+        for input in &graph.input {
+            if producers.contains_key(input.name.as_slice()) {
+                continue;
+            }
+            producers.insert(input.name.as_slice(), (input.name.as_slice(), 0));
+            ops.push(Op {
+                name: names.decode(&input.name)?,
+                ty: "Input".to_string(),
+                inputs: vec![],
+                control_inputs: vec![],
+                output_dims: vec![value_info_dims(input)],
+                parameters: 0,
+                attributes: BTreeMap::new(),
+                call: None,
+                branches: vec![],
+            });
+        }
+        for node in &graph.node {
+            for (i, output) in node.output.iter().enumerate() {
+                producers.insert(output.as_slice(), (node_name(node), i));
+            }
+        }
+
+        for node in &graph.node {
+            let mut inputs = vec![];
+            for input in &node.input {
+                // Empty names are used for omitted optional inputs so won't have a producer
+                if let Some((op, index)) = producers.get(input.as_slice()) {
+                    inputs.push(Port {
+                        op: names.decode(op)?,
+                        index: *index,
+                        output_arg: None,
+                        dim: shapes.get(input.as_slice()).cloned().flatten(),
+                    });
+                }
+            }
+            ops.push(Op {
+                name: names.decode(node_name(node))?,
+                ty: names.decode(&node.op_type)?,
+                inputs,
+                control_inputs: vec![],
+                output_dims: node
+                    .output
+                    .iter()
+                    .map(|output| shapes.get(output.as_slice()).cloned().flatten())
+                    .collect(),
+                parameters: 0,
+                attributes: BTreeMap::new(),
+                call: None,
+                branches: vec![],
+            });
+        }
+        Ok(ops)
+    }
+}
+
+/// The subset of the ONNX protobuf schema (onnx.proto3) needed to build the graph, anything else
+/// is skipped over when decoding. Names are kept as bytes so invalid UTF-8 doesn't fail decoding.
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ModelProto {
+        #[prost(message, optional, tag = "7")]
+        pub graph: Option<GraphProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GraphProto {
+        #[prost(message, repeated, tag = "1")]
+        pub node: Vec<NodeProto>,
+        #[prost(bytes, tag = "2")]
+        pub name: Vec<u8>,
+        #[prost(message, repeated, tag = "5")]
+        pub initializer: Vec<TensorProto>,
+        #[prost(message, repeated, tag = "11")]
+        pub input: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "12")]
+        pub output: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "13")]
+        pub value_info: Vec<ValueInfoProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NodeProto {
+        #[prost(bytes, repeated, tag = "1")]
+        pub input: Vec<Vec<u8>>,
+        #[prost(bytes, repeated, tag = "2")]
+        pub output: Vec<Vec<u8>>,
+        #[prost(bytes, tag = "3")]
+        pub name: Vec<u8>,
+        #[prost(bytes, tag = "4")]
+        pub op_type: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorProto {
+        #[prost(int64, repeated, tag = "1")]
+        pub dims: Vec<i64>,
+        #[prost(bytes, tag = "8")]
+        pub name: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ValueInfoProto {
+        #[prost(bytes, tag = "1")]
+        pub name: Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub r#type: Option<TypeProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TypeProto {
+        /// Member of the `value` oneof, the other kinds of value aren't tensors so have no shape
+        #[prost(message, optional, tag = "1")]
+        pub tensor_type: Option<TensorTypeProto>,
+    }
+
+    /// `TypeProto.Tensor` in the schema.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorTypeProto {
+        #[prost(int32, tag = "1")]
+        pub elem_type: i32,
+        #[prost(message, optional, tag = "2")]
+        pub shape: Option<TensorShapeProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorShapeProto {
+        #[prost(message, repeated, tag = "1")]
+        pub dim: Vec<Dimension>,
+    }
+
+    /// `TensorShapeProto.Dimension` in the schema, `dim_value` and `dim_param` form a oneof.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Dimension {
+        #[prost(int64, optional, tag = "1")]
+        pub dim_value: Option<i64>,
+        /// A named dimension, which isn't used but is kept as bytes like the names
+        #[prost(bytes, optional, tag = "2")]
+        pub dim_param: Option<Vec<u8>>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::*;
+
+    fn value_info(name: &str, dims: &[Dimension]) -> ValueInfoProto {
+        ValueInfoProto {
+            name: name.into(),
+            r#type: Some(TypeProto {
+                tensor_type: Some(TensorTypeProto {
+                    elem_type: 1,
+                    shape: Some(TensorShapeProto { dim: dims.to_vec() }),
+                }),
+            }),
+        }
+    }
+
+    fn dim(value: i64) -> Dimension {
+        Dimension {
+            dim_value: Some(value),
+            dim_param: None,
+        }
+    }
+
+    fn node(name: &str, op_type: &str, inputs: &[&str], outputs: &[&str]) -> NodeProto {
+        NodeProto {
+            input: inputs.iter().map(|i| i.as_bytes().to_vec()).collect(),
+            output: outputs.iter().map(|o| o.as_bytes().to_vec()).collect(),
+            name: name.into(),
+            op_type: op_type.into(),
+        }
+    }
+
+    fn ops(graph: GraphProto) -> Vec<Op> {
+        let bytes = ModelProto { graph: Some(graph) }.encode_to_vec();
+        let model = Model::decode(&bytes).unwrap();
+        model.ops(&mut NameDecoder::new(false)).unwrap()
+    }
+
+    #[test]
+    fn reads_nodes() {
+        // The batch dimension is named with bytes which aren't valid UTF-8
+        let batch = Dimension {
+            dim_value: None,
+            dim_param: Some(b"batch\xff".to_vec()),
+        };
+        let graph = GraphProto {
+            node: vec![
+                node("/model/fc/Gemm", "Gemm", &["x", "W", ""], &["h"]),
+                node("", "Relu", &["h"], &["y"]),
+            ],
+            name: b"main".to_vec(),
+            initializer: vec![TensorProto {
+                dims: vec![3, 4],
+                name: b"W".to_vec(),
+            }],
+            // Older exporters list the initializers as inputs too
+            input: vec![
+                value_info("x", &[batch, dim(3)]),
+                value_info("W", &[dim(3), dim(4)]),
+            ],
+            output: vec![value_info("y", &[dim(2), dim(4)])],
+            value_info: vec![],
+        };
+        let ops = ops(graph);
+
+        let summary = ops
+            .iter()
+            .map(|op| (op.name.as_str(), op.ty.as_str(), op.parameters))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("W", "Initializer", 12),
+                ("x", "Input", 0),
+                ("model/fc/Gemm", "Gemm", 0),
+                ("y", "Relu", 0),
+            ]
+        );
+        // The omitted optional input is left out
+        let inputs = ops[2]
+            .inputs
+            .iter()
+            .map(|port| (port.op.as_str(), port.index, port.dim.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            vec![
+                ("x", 0, Some(vec![None, Some(3)])),
+                ("W", 0, Some(vec![Some(3), Some(4)])),
+            ]
+        );
+        assert_eq!(ops[3].inputs[0].op, "model/fc/Gemm");
+        assert_eq!(ops[3].output_dims, vec![Some(vec![Some(2), Some(4)])]);
+    }
+}
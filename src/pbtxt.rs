@@ -0,0 +1,690 @@
+//! Converts text format GraphDef and SavedModel protobufs (`.pbtxt`) into the binary encoding.
+//! The conversion is driven by a description of the schema so the binary can be handed to
+//! anything expecting the normal encoding.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+/// An error in a text format protobuf, with the line it was found on.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Checks whether the start of a file looks like a text format protobuf rather than a binary
+/// one. Binary protobufs are almost guaranteed to contain control characters in their tags and
+/// lengths.
+pub fn is_text(bytes: &[u8]) -> bool {
+    let start = &bytes[..bytes.len().min(512)];
+    let text_bytes = start
+        .iter()
+        .all(|b| !b.is_ascii_control() || b.is_ascii_whitespace());
+    let first = start.iter().find(|b| !b.is_ascii_whitespace());
+    text_bytes && first.is_some_and(|b| b.is_ascii_alphabetic() || *b == b'#')
+}
+
+/// Converts a text format GraphDef into a binary encoded one.
+pub fn graph_def_to_binary(text: &str) -> Result<Vec<u8>, ParseError> {
+    to_binary(text, Message::GraphDef)
+}
+
+/// Converts a text format SavedModel into a binary encoded one, only the tags and graphs of the
+/// meta graphs are kept.
+pub fn saved_model_to_binary(text: &str) -> Result<Vec<u8>, ParseError> {
+    to_binary(text, Message::SavedModel)
+}
+
+fn to_binary(text: &str, message: Message) -> Result<Vec<u8>, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    parser.message(message, None)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Message {
+    SavedModel,
+    MetaGraphDef,
+    MetaInfoDef,
+    GraphDef,
+    NodeDef,
+    AttrEntry,
+    StringEntry,
+    AttrValue,
+    ListValue,
+    NameAttrList,
+    TensorShapeProto,
+    Dim,
+    TensorProto,
+    VersionDef,
+    FunctionDefLibrary,
+    FunctionDef,
+    GradientDef,
+    OpDef,
+    ArgDef,
+    AttrDef,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Message(Message),
+    Bytes,
+    Int32,
+    Int64,
+    UInt32,
+    UInt64,
+    Float,
+    Double,
+    Bool,
+    DataType,
+}
+
+/// Field number and type of a field in one of the GraphDef or SavedModel messages, `None` for
+/// fields which aren't needed and can be dropped.
+fn field(message: Message, name: &str) -> Option<(u32, Kind)> {
+    use self::Kind::*;
+    use self::Message as M;
+    let field = match (message, name) {
+        (M::SavedModel, "saved_model_schema_version") => (1, Int64),
+        (M::SavedModel, "meta_graphs") => (2, Message(M::MetaGraphDef)),
+        (M::MetaGraphDef, "meta_info_def") => (1, Message(M::MetaInfoDef)),
+        (M::MetaGraphDef, "graph_def") => (2, Message(M::GraphDef)),
+        (M::MetaInfoDef, "tags") => (4, Bytes),
+        (M::GraphDef, "node") => (1, Message(M::NodeDef)),
+        (M::GraphDef, "library") => (2, Message(M::FunctionDefLibrary)),
+        (M::GraphDef, "version") => (3, Int32),
+        (M::GraphDef, "versions") => (4, Message(M::VersionDef)),
+        (M::NodeDef, "name") => (1, Bytes),
+        (M::NodeDef, "op") => (2, Bytes),
+        (M::NodeDef, "input") => (3, Bytes),
+        (M::NodeDef, "device") => (4, Bytes),
+        (M::NodeDef, "attr") => (5, Message(M::AttrEntry)),
+        (M::AttrEntry, "key") => (1, Bytes),
+        (M::AttrEntry, "value") => (2, Message(M::AttrValue)),
+        (M::StringEntry, "key") => (1, Bytes),
+        (M::StringEntry, "value") => (2, Bytes),
+        (M::AttrValue, "list") => (1, Message(M::ListValue)),
+        (M::AttrValue, "s") => (2, Bytes),
+        (M::AttrValue, "i") => (3, Int64),
+        (M::AttrValue, "f") => (4, Float),
+        (M::AttrValue, "b") => (5, Bool),
+        (M::AttrValue, "type") => (6, DataType),
+        (M::AttrValue, "shape") => (7, Message(M::TensorShapeProto)),
+        (M::AttrValue, "tensor") => (8, Message(M::TensorProto)),
+        (M::AttrValue, "placeholder") => (9, Bytes),
+        (M::AttrValue, "func") => (10, Message(M::NameAttrList)),
+        (M::ListValue, "s") => (2, Bytes),
+        (M::ListValue, "i") => (3, Int64),
+        (M::ListValue, "f") => (4, Float),
+        (M::ListValue, "b") => (5, Bool),
+        (M::ListValue, "type") => (6, DataType),
+        (M::ListValue, "shape") => (7, Message(M::TensorShapeProto)),
+        (M::ListValue, "tensor") => (8, Message(M::TensorProto)),
+        (M::ListValue, "func") => (9, Message(M::NameAttrList)),
+        (M::NameAttrList, "name") => (1, Bytes),
+        (M::NameAttrList, "attr") => (2, Message(M::AttrEntry)),
+        (M::TensorShapeProto, "dim") => (2, Message(M::Dim)),
+        (M::TensorShapeProto, "unknown_rank") => (3, Bool),
+        (M::Dim, "size") => (1, Int64),
+        (M::Dim, "name") => (2, Bytes),
+        (M::TensorProto, "dtype") => (1, DataType),
+        (M::TensorProto, "tensor_shape") => (2, Message(M::TensorShapeProto)),
+        (M::TensorProto, "version_number") => (3, Int32),
+        (M::TensorProto, "tensor_content") => (4, Bytes),
+        (M::TensorProto, "float_val") => (5, Float),
+        (M::TensorProto, "double_val") => (6, Double),
+        (M::TensorProto, "int_val") => (7, Int32),
+        (M::TensorProto, "string_val") => (8, Bytes),
+        (M::TensorProto, "scomplex_val") => (9, Float),
+        (M::TensorProto, "int64_val") => (10, Int64),
+        (M::TensorProto, "bool_val") => (11, Bool),
+        (M::TensorProto, "dcomplex_val") => (12, Double),
+        (M::TensorProto, "half_val") => (13, Int32),
+        (M::TensorProto, "uint32_val") => (16, UInt32),
+        (M::TensorProto, "uint64_val") => (17, UInt64),
+        (M::VersionDef, "producer") => (1, Int32),
+        (M::VersionDef, "min_consumer") => (2, Int32),
+        (M::VersionDef, "bad_consumers") => (3, Int32),
+        (M::FunctionDefLibrary, "function") => (1, Message(M::FunctionDef)),
+        (M::FunctionDefLibrary, "gradient") => (2, Message(M::GradientDef)),
+        (M::GradientDef, "function_name") => (1, Bytes),
+        (M::GradientDef, "gradient_func") => (2, Bytes),
+        (M::FunctionDef, "signature") => (1, Message(M::OpDef)),
+        (M::FunctionDef, "node_def") => (3, Message(M::NodeDef)),
+        (M::FunctionDef, "ret") => (4, Message(M::StringEntry)),
+        (M::FunctionDef, "attr") => (5, Message(M::AttrEntry)),
+        (M::FunctionDef, "control_ret") => (6, Message(M::StringEntry)),
+        (M::OpDef, "name") => (1, Bytes),
+        (M::OpDef, "input_arg") => (2, Message(M::ArgDef)),
+        (M::OpDef, "output_arg") => (3, Message(M::ArgDef)),
+        (M::OpDef, "attr") => (4, Message(M::AttrDef)),
+        (M::OpDef, "summary") => (5, Bytes),
+        (M::OpDef, "description") => (6, Bytes),
+        (M::OpDef, "is_aggregate") => (16, Bool),
+        (M::OpDef, "is_stateful") => (17, Bool),
+        (M::OpDef, "is_commutative") => (18, Bool),
+        (M::OpDef, "allows_uninitialized_input") => (19, Bool),
+        (M::OpDef, "control_output") => (20, Bytes),
+        (M::ArgDef, "name") => (1, Bytes),
+        (M::ArgDef, "description") => (2, Bytes),
+        (M::ArgDef, "type") => (3, DataType),
+        (M::ArgDef, "type_attr") => (4, Bytes),
+        (M::ArgDef, "number_attr") => (5, Bytes),
+        (M::ArgDef, "type_list_attr") => (6, Bytes),
+        (M::ArgDef, "is_ref") => (16, Bool),
+        (M::AttrDef, "name") => (1, Bytes),
+        (M::AttrDef, "type") => (2, Bytes),
+        (M::AttrDef, "default_value") => (3, Message(M::AttrValue)),
+        (M::AttrDef, "description") => (4, Bytes),
+        (M::AttrDef, "has_minimum") => (5, Bool),
+        (M::AttrDef, "minimum") => (6, Int64),
+        (M::AttrDef, "allowed_values") => (7, Message(M::AttrValue)),
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Names of the `DataType` enum values.
+const DATA_TYPES: &[&str] = &[
+    "DT_INVALID",
+    "DT_FLOAT",
+    "DT_DOUBLE",
+    "DT_INT32",
+    "DT_UINT8",
+    "DT_INT16",
+    "DT_INT8",
+    "DT_STRING",
+    "DT_COMPLEX64",
+    "DT_INT64",
+    "DT_BOOL",
+    "DT_QINT8",
+    "DT_QUINT8",
+    "DT_QINT32",
+    "DT_BFLOAT16",
+    "DT_QINT16",
+    "DT_QUINT16",
+    "DT_UINT16",
+    "DT_COMPLEX128",
+    "DT_HALF",
+    "DT_RESOURCE",
+    "DT_VARIANT",
+    "DT_UINT32",
+    "DT_UINT64",
+];
+
+/// Value of a `DataType` enum name, the `_REF` variants are offset by 100.
+fn data_type(name: &str) -> Option<i64> {
+    let (name, offset) = match name.strip_suffix("_REF") {
+        Some(name) => (name, 100),
+        None => (name, 0),
+    };
+    DATA_TYPES
+        .iter()
+        .position(|ty| *ty == name)
+        .map(|ty| ty as i64 + offset)
+}
+
+/// Name of a `DataType` enum value, the inverse of `data_type`.
+pub(crate) fn data_type_name(value: i32) -> String {
+    let (value, suffix) = if value >= 100 {
+        (value - 100, "_REF")
+    } else {
+        (value, "")
+    };
+    usize::try_from(value)
+        .ok()
+        .and_then(|v| DATA_TYPES.get(v))
+        .map_or_else(|| format!("DT_{}", value), |ty| format!("{}{}", ty, suffix))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(Vec<u8>),
+    Punct(char),
+}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError {
+        line,
+        message: message.into(),
+    })
+}
+
+fn unescape(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    line: usize,
+) -> Result<Vec<u8>, ParseError> {
+    let mut bytes = vec![];
+    let c = match chars.next() {
+        Some(c) => c,
+        None => return error(line, "unterminated string"),
+    };
+    let byte = match c {
+        'a' => 0x07,
+        'b' => 0x08,
+        'f' => 0x0c,
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        'v' => 0x0b,
+        '0'..='7' => {
+            let mut value = c.to_digit(8).unwrap_or(0);
+            for _ in 0..2 {
+                match chars.peek().and_then(|c| c.to_digit(8)) {
+                    Some(d) => {
+                        value = value * 8 + d;
+                        chars.next();
+                    }
+                    None => break,
+                }
+            }
+            match u8::try_from(value) {
+                Ok(value) => value,
+                Err(_) => {
+                    return error(line, format!("octal escape \\{:o} is out of range", value))
+                }
+            }
+        }
+        'x' => {
+            let mut value = 0;
+            let mut digits = 0;
+            while digits < 2 {
+                match chars.peek().and_then(|c| c.to_digit(16)) {
+                    Some(d) => {
+                        value = value * 16 + d;
+                        digits += 1;
+                        chars.next();
+                    }
+                    None => break,
+                }
+            }
+            if digits == 0 {
+                return error(line, "\\x escape has no hex digits");
+            }
+            value as u8
+        }
+        c => {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            return Ok(bytes);
+        }
+    };
+    bytes.push(byte);
+    Ok(bytes)
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            '"' | '\'' => {
+                let mut bytes = vec![];
+                loop {
+                    match chars.next() {
+                        Some('\\') => bytes.extend(unescape(&mut chars, line)?),
+                        Some(q) if q == c => break,
+                        Some('\n') | None => return error(line, "unterminated string"),
+                        Some(other) => {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+                        }
+                    }
+                }
+                tokens.push((Token::Str(bytes), line));
+            }
+            '{' | '}' | '<' | '>' | '[' | ']' | ':' | ',' | ';' => {
+                tokens.push((Token::Punct(c), line));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '+' || c == '.' => {
+                let mut ident = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || c == '+' {
+                        ident.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((Token::Ident(ident), line));
+            }
+            c => return error(line, format!("unexpected character '{}'", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_tag(number: u32, wire_type: u8, out: &mut Vec<u8>) {
+    write_varint(u64::from(number) << 3 | u64::from(wire_type), out);
+}
+
+fn write_bytes(number: u32, bytes: &[u8], out: &mut Vec<u8>) {
+    write_tag(number, 2, out);
+    write_varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+fn parse_int(s: &str) -> Option<i64> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse::<u64>().ok()?
+    };
+    Some(if negative {
+        (value as i64).wrapping_neg()
+    } else {
+        value as i64
+    })
+}
+
+fn parse_float(s: &str) -> Option<f64> {
+    let lower = s.to_ascii_lowercase();
+    let (negative, value) = match lower.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, lower.as_str()),
+    };
+    let value = match value {
+        "inf" | "infinity" => f64::INFINITY,
+        "nan" => f64::NAN,
+        value => value
+            .strip_suffix('f')
+            .unwrap_or(value)
+            .parse::<f64>()
+            .ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        match self.tokens.get(self.pos) {
+            Some((token, _)) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => error(self.line(), "unexpected end of file"),
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses the fields of a message until the closing bracket, or the end of the file for the
+    /// top level message, returning the binary encoding.
+    fn message(&mut self, message: Message, close: Option<char>) -> Result<Vec<u8>, ParseError> {
+        let mut out = vec![];
+        loop {
+            match (self.peek(), close) {
+                (None, None) => return Ok(out),
+                (Some(Token::Punct(c)), Some(close)) if *c == close => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                _ => {}
+            }
+            let line = self.line();
+            let name = match self.next()? {
+                Token::Ident(name) => name,
+                token => return error(line, format!("expected a field name, found {:?}", token)),
+            };
+            let field = field(message, &name);
+            let has_colon = self.eat(':');
+            if self.eat('[') {
+                if !self.eat(']') {
+                    loop {
+                        self.value(field, &mut out)?;
+                        if self.eat(']') {
+                            break;
+                        }
+                        if !self.eat(',') {
+                            return error(self.line(), "expected ',' or ']' in list");
+                        }
+                    }
+                }
+            } else if !has_colon && !matches!(self.peek(), Some(Token::Punct('{' | '<'))) {
+                return error(self.line(), format!("expected ':' after '{}'", name));
+            } else {
+                self.value(field, &mut out)?;
+            }
+            // Fields can optionally be separated by commas or semicolons
+            if !self.eat(',') {
+                self.eat(';');
+            }
+        }
+    }
+
+    /// Parses a single value of a field and appends its encoding, fields without a schema entry
+    /// are parsed and thrown away.
+    fn value(&mut self, field: Option<(u32, Kind)>, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        let line = self.line();
+        let close = if self.eat('{') {
+            Some('}')
+        } else if self.eat('<') {
+            Some('>')
+        } else {
+            None
+        };
+        if let Some(close) = close {
+            return match field {
+                Some((number, Kind::Message(message))) => {
+                    let bytes = self.message(message, Some(close))?;
+                    write_bytes(number, &bytes, out);
+                    Ok(())
+                }
+                Some(_) => error(line, "expected a value but found a message"),
+                None => self.skip_message(close),
+            };
+        }
+        let token = self.next()?;
+        let (number, kind) = match field {
+            Some(field) => field,
+            None => {
+                // Adjacent strings are concatenated
+                while let Some(Token::Str(_)) = self.peek() {
+                    self.pos += 1;
+                }
+                return Ok(());
+            }
+        };
+        let ident = match token {
+            Token::Str(mut bytes) => {
+                if kind != Kind::Bytes {
+                    return error(line, "unexpected string");
+                }
+                while let Some(Token::Str(more)) = self.peek() {
+                    bytes.extend_from_slice(more);
+                    self.pos += 1;
+                }
+                write_bytes(number, &bytes, out);
+                return Ok(());
+            }
+            Token::Ident(ident) => ident,
+            Token::Punct(c) => return error(line, format!("unexpected '{}'", c)),
+        };
+        let invalid = || ParseError {
+            line,
+            message: format!("invalid value '{}'", ident),
+        };
+        match kind {
+            Kind::Int32 | Kind::Int64 | Kind::UInt32 | Kind::UInt64 => {
+                let value = parse_int(&ident).ok_or_else(invalid)?;
+                write_tag(number, 0, out);
+                write_varint(value as u64, out);
+            }
+            Kind::DataType => {
+                let value = data_type(&ident)
+                    .or_else(|| parse_int(&ident))
+                    .ok_or_else(invalid)?;
+                write_tag(number, 0, out);
+                write_varint(value as u64, out);
+            }
+            Kind::Bool => {
+                let value = match ident.as_str() {
+                    "true" | "True" | "t" | "1" => 1,
+                    "false" | "False" | "f" | "0" => 0,
+                    _ => return Err(invalid()),
+                };
+                write_tag(number, 0, out);
+                write_varint(value, out);
+            }
+            Kind::Float => {
+                let value = parse_float(&ident).ok_or_else(invalid)? as f32;
+                write_tag(number, 5, out);
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+            Kind::Double => {
+                let value = parse_float(&ident).ok_or_else(invalid)?;
+                write_tag(number, 1, out);
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+            Kind::Bytes | Kind::Message(_) => return Err(invalid()),
+        }
+        Ok(())
+    }
+
+    fn skip_message(&mut self, close: char) -> Result<(), ParseError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()? {
+                Token::Punct('{' | '<') => depth += 1,
+                Token::Punct(c) if c == close || c == '}' || c == '>' => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(fields: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        write_bytes(1, fields, &mut out);
+        out
+    }
+
+    #[test]
+    fn unescapes_strings() {
+        let binary = graph_def_to_binary(r#"node { name: "a\"\\\n\t\101\x41\377" op: 'b' "c" }"#);
+        let mut fields = vec![];
+        write_bytes(1, b"a\"\\\n\tAA\xff", &mut fields);
+        write_bytes(2, b"bc", &mut fields);
+        assert_eq!(binary, Ok(node(&fields)));
+    }
+
+    #[test]
+    fn octal_escape_out_of_range() {
+        let err = graph_def_to_binary("\nnode { name: \"\\400\" }").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn hex_escape_without_digits() {
+        let err = graph_def_to_binary("node {\n name: \"\\xg\" }").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn repeated_fields_as_lists() {
+        assert_eq!(
+            graph_def_to_binary(r#"node { input: ["a", "b"] } node { input: [] }"#),
+            graph_def_to_binary(r#"node { input: "a" input: "b" } node { }"#)
+        );
+    }
+
+    #[test]
+    fn angle_bracket_messages() {
+        assert_eq!(
+            graph_def_to_binary(r#"node < name: "a" attr < key: "T" value { i: 1 } > >"#),
+            graph_def_to_binary(r#"node { name: "a" attr { key: "T" value { i: 1 } } }"#)
+        );
+    }
+
+    #[test]
+    fn skips_unknown_fields() {
+        let text = r#"
+            node {
+                name: "a"
+                experimental_debug_info { original_node_names: ["b", "c"] }
+                unknown: "d" 'e'
+                other < nested { x: 1 } >;
+            }
+        "#;
+        assert_eq!(
+            graph_def_to_binary(text),
+            graph_def_to_binary(r#"node { name: "a" }"#)
+        );
+    }
+
+    #[test]
+    fn reference_data_types() {
+        assert_eq!(data_type("DT_FLOAT_REF"), Some(101));
+        assert_eq!(data_type_name(101), "DT_FLOAT_REF");
+        assert_eq!(data_type_name(3), "DT_INT32");
+        assert_eq!(data_type_name(100), "DT_INVALID_REF");
+        assert_eq!(
+            graph_def_to_binary(r#"node { attr { key: "T" value { type: DT_INT32_REF } } }"#),
+            graph_def_to_binary(r#"node { attr { key: "T" value { type: 103 } } }"#)
+        );
+    }
+}
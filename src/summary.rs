@@ -0,0 +1,148 @@
+//! Renders a table summarising the graph like Keras' `model.summary()`, with a row for each node
+//! giving its output shapes, parameter count and the nodes it's connected to. Used with a
+//! `max_depth` each row is a name scope block at that depth.
+use crate::graph::{format_shape, Edge, EdgeKind, Node};
+use crate::text::topological_order;
+use petgraph::graph::Graph;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::BTreeSet;
+
+/// How the summary table is written out.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum TableStyle {
+    /// Aligned columns for reading in a terminal
+    Text,
+    /// A Markdown table
+    Markdown,
+    /// Comma separated values, with the totals as a final row
+    Csv,
+}
+
+const HEADER: [&str; 5] = ["Layer", "Type", "Output Shape", "Param #", "Connected to"];
+
+fn rows(graph: &Graph<Node, Edge>) -> Vec<[String; 5]> {
+    topological_order(graph)
+        .into_iter()
+        .map(|idx| {
+            let node = &graph[idx];
+            // The op's own shapes cover outputs nothing uses, such as the outputs of the model.
+            // Otherwise each distinct output is only listed once even if it's used by several
+            // nodes, and an op with neither has an unknown shape
+            let mut outputs = if node.output_dims().is_empty() {
+                graph
+                    .edges_directed(idx, Direction::Outgoing)
+                    .filter(|e| matches!(e.weight().kind(), EdgeKind::Data | EdgeKind::Back))
+                    .map(|e| (e.weight().output_index(), format_shape(e.weight().dim())))
+                    .collect::<BTreeSet<_>>()
+            } else {
+                node.output_dims()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, dims)| (Some(i), format_shape(dims.as_deref())))
+                    .collect()
+            };
+            if outputs.is_empty() {
+                outputs.insert((None, format_shape(None)));
+            }
+            let mut inputs = graph
+                .edges_directed(idx, Direction::Incoming)
+                .filter(|e| matches!(e.weight().kind(), EdgeKind::Data | EdgeKind::Back))
+                .collect::<Vec<_>>();
+            inputs.sort_by_key(|e| e.weight().input_index());
+            let connected = inputs
+                .into_iter()
+                .map(|e| {
+                    let source = graph[e.source()].name().to_string_lossy();
+                    match e.weight().output_index() {
+                        Some(index) => format!("{}:{}", source, index),
+                        None => source.into_owned(),
+                    }
+                })
+                .collect::<Vec<_>>();
+            [
+                node.name().to_string_lossy().into_owned(),
+                node.ty().to_string(),
+                outputs
+                    .into_iter()
+                    .map(|(_, dims)| dims)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                node.parameters().to_string(),
+                connected.join(", "),
+            ]
+        })
+        .collect()
+}
+
+fn text_table(rows: &[[String; 5]], total: usize) -> String {
+    let mut widths = HEADER.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[&str]| {
+        let cells = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>();
+        format!("{}\n", cells.join("  ").trim_end())
+    };
+    let rule = |c: &str| format!("{}\n", c.repeat(widths.iter().sum::<usize>() + 2 * 4));
+    let mut out = line(&HEADER);
+    out.push_str(&rule("="));
+    for row in rows {
+        out.push_str(&line(&row.each_ref().map(String::as_str)));
+    }
+    out.push_str(&rule("="));
+    out.push_str(&format!("Total params: {}\n", total));
+    out
+}
+
+fn markdown_table(rows: &[[String; 5]], total: usize) -> String {
+    let escape = |cell: &str| cell.replace('|', "\\|");
+    let line = |cells: &[&str]| {
+        let cells = cells.iter().map(|cell| escape(cell)).collect::<Vec<_>>();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let mut out = line(&HEADER);
+    out.push_str("|---|---|---|--:|---|\n");
+    for row in rows {
+        out.push_str(&line(&row.each_ref().map(String::as_str)));
+    }
+    out.push_str(&format!("\n**Total params:** {}\n", total));
+    out
+}
+
+fn csv_table(rows: &[[String; 5]], total: usize) -> String {
+    let escape = |cell: &str| {
+        if cell.contains([',', '"', '\n']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.to_string()
+        }
+    };
+    let line = |cells: &[&str]| {
+        let cells = cells.iter().map(|cell| escape(cell)).collect::<Vec<_>>();
+        format!("{}\n", cells.join(","))
+    };
+    let mut out = line(&HEADER);
+    for row in rows {
+        out.push_str(&line(&row.each_ref().map(String::as_str)));
+    }
+    out.push_str(&line(&["Total", "", "", &total.to_string(), ""]));
+    out
+}
+
+/// Renders the summary table for the graph.
+pub fn render(graph: &Graph<Node, Edge>, style: TableStyle) -> String {
+    let rows = rows(graph);
+    let total = graph.node_weights().map(Node::parameters).sum();
+    match style {
+        TableStyle::Text => text_table(&rows, total),
+        TableStyle::Markdown => markdown_table(&rows, total),
+        TableStyle::Csv => csv_table(&rows, total),
+    }
+}
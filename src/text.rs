@@ -0,0 +1,163 @@
+//! Renders graphs as plain text for reading in a terminal, such as over SSH on a machine without
+//! graphviz.
+//!
+//! Ops are listed in topological order with each name scope indented under a single header, and
+//! every op lists the ops feeding into it:
+//!
+//! ```text
+//! x (Placeholder)
+//! dense/
+//!   w (Const)
+//!   MatMul (MatMul) <- x:0 [?, 3], dense/w:0 [3, 4]
+//! ```
+use crate::dot::name_scope;
+use crate::graph::{Edge, EdgeKind, Node};
+use petgraph::graph::{EdgeReference, Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Write};
+
+/// Sorts the nodes so ops come after their inputs, ignoring back edges. Any other cycles are broken
+/// by taking the first remaining node, and ties are kept in the order the nodes were added.
+pub(crate) fn topological_order(graph: &Graph<Node, Edge>) -> Vec<NodeIndex> {
+    let forward = |edge: &EdgeReference<Edge>| edge.weight().kind() != EdgeKind::Back;
+    let mut inputs = graph
+        .node_indices()
+        .map(|node| {
+            graph
+                .edges_directed(node, Direction::Incoming)
+                .filter(forward)
+                .count()
+        })
+        .collect::<Vec<_>>();
+    let mut ready = graph
+        .node_indices()
+        .filter(|node| inputs[node.index()] == 0)
+        .collect::<BTreeSet<_>>();
+    let mut placed = vec![false; graph.node_count()];
+    // Every node before the cursor has been placed, so cycles are broken without rescanning
+    let mut cursor = 0;
+    let mut order = Vec::with_capacity(graph.node_count());
+    while order.len() < graph.node_count() {
+        let node = match ready.pop_first() {
+            Some(node) => node,
+            None => {
+                while placed[cursor] {
+                    cursor += 1;
+                }
+                NodeIndex::new(cursor)
+            }
+        };
+        if placed[node.index()] {
+            continue;
+        }
+        placed[node.index()] = true;
+        order.push(node);
+        for edge in graph.edges(node).filter(forward) {
+            let target = edge.target().index();
+            if !placed[target] {
+                inputs[target] -= 1;
+                if inputs[target] == 0 {
+                    ready.insert(edge.target());
+                }
+            }
+        }
+    }
+    order
+}
+
+fn input_reference(graph: &Graph<Node, Edge>, edge: EdgeReference<Edge>) -> String {
+    let source = graph[edge.source()].name().to_string_lossy();
+    let edge = edge.weight();
+    let port = edge
+        .output_index()
+        .map(|i| format!(":{}", i))
+        .unwrap_or_default();
+    match edge.kind() {
+        EdgeKind::Data => format!("{}{} {}", source, port, edge),
+        EdgeKind::Control => format!("^{}", source),
+        EdgeKind::Back => format!("{}{} {} (next iteration)", source, port, edge),
+        EdgeKind::Call => format!("{} (call)", source),
+    }
+}
+
+/// Orders the ops topologically, then groups them by name scope so each scope is listed in one
+/// place. Scopes and ops in the same scope are ordered by where they first appear.
+fn scope_order(graph: &Graph<Node, Edge>) -> Vec<(NodeIndex, Vec<String>)> {
+    let order = topological_order(graph)
+        .into_iter()
+        .map(|node| (node, name_scope(&graph[node])))
+        .collect::<Vec<_>>();
+    let mut first = HashMap::new();
+    for (i, (_, scope)) in order.iter().enumerate() {
+        for depth in 1..=scope.len() {
+            first.entry(&scope[..depth]).or_insert(i);
+        }
+    }
+    let mut keyed = order
+        .iter()
+        .enumerate()
+        .map(|(i, (_, scope))| {
+            let mut key = (1..=scope.len())
+                .map(|depth| first[&scope[..depth]])
+                .collect::<Vec<_>>();
+            key.push(i);
+            key
+        })
+        .zip(order.iter().cloned())
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    keyed.into_iter().map(|(_, op)| op).collect()
+}
+
+fn write_text(graph: &Graph<Node, Edge>, out: &mut String) -> fmt::Result {
+    let mut current: Vec<String> = vec![];
+    for (node, scope) in scope_order(graph) {
+        let weight = &graph[node];
+        let common = current
+            .iter()
+            .zip(&scope)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for (depth, name) in scope.iter().enumerate().skip(common) {
+            writeln!(out, "{}{}/", "  ".repeat(depth), name)?;
+        }
+        current = scope;
+        let indent = "  ".repeat(current.len());
+        let name = weight
+            .name()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        write!(out, "{}{} ({})", indent, name, weight.ty())?;
+        let mut inputs = graph
+            .edges_directed(node, Direction::Incoming)
+            .collect::<Vec<_>>();
+        inputs.sort_by_key(|edge| (edge.weight().kind(), edge.weight().input_index()));
+        let inputs = inputs
+            .into_iter()
+            .map(|edge| input_reference(graph, edge))
+            .collect::<Vec<_>>();
+        if !inputs.is_empty() {
+            write!(out, " <- {}", inputs.join(", "))?;
+        }
+        if !weight.region().is_empty() {
+            write!(out, " {{{}}}", weight.region().join(", "))?;
+        }
+        out.push('\n');
+        if let Some(summary) = weight.summary() {
+            for line in summary.to_string().lines() {
+                writeln!(out, "{}  {}", indent, line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders the graph as an indented list of ops in topological order.
+pub fn render(graph: &Graph<Node, Edge>) -> String {
+    let mut out = String::new();
+    write_text(graph, &mut out).expect("writing to a string can't fail");
+    out
+}
@@ -0,0 +1,153 @@
+use crate::error::{Error, InvalidNameError};
+use crate::graph_def;
+use crate::model::{Function, ModelGraph, NameDecoder, Op, Port};
+use crate::pbtxt;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::os::raw::c_int;
+use std::path::Path;
+use tensorflow::{
+    Graph as TfGraph, ImportGraphDefOptions, Operation, Output, SavedModelBundle, SessionOptions,
+    Shape, Status,
+};
+
+/// TensorFlow checks op names and types when a graph is imported so these shouldn't fail. The raw
+/// bytes aren't available through the C API, so invalid names can't be escaped and are always an
+/// error, identified by `context` instead.
+fn op_name(op: &Operation, context: impl FnOnce() -> String) -> Result<String, InvalidNameError> {
+    op.name().map_err(|_| InvalidNameError { name: context() })
+}
+
+fn shape_dims(shape: Shape) -> Option<Vec<Option<usize>>> {
+    let dims = Option::<Vec<_>>::from(shape)?
+        .into_iter()
+        .map(|d| d.and_then(|d| usize::try_from(d).ok()))
+        .collect();
+    Some(dims)
+}
+
+/// Gets the shape of an operation output using the graph's shape inference. Unknown dimensions
+/// are `None` and if the rank is unknown the whole shape is `None`.
+fn output_dims(nn_graph: &TfGraph, op: &Operation, index: usize) -> Option<Vec<Option<usize>>> {
+    let output = Output {
+        operation: op.clone(),
+        index: index as c_int,
+    };
+    nn_graph.tensor_shape(output).ok().and_then(shape_dims)
+}
+
+/// The C API only exposes the function library and the functions, attributes and parameters of
+/// ops through the GraphDef, so those are read from the serialised graph.
+fn decode_graph_def(graph: &TfGraph) -> Option<graph_def::Model> {
+    let bytes = graph.to_graph_def().ok()?;
+    graph_def::Model::decode(&bytes).ok()
+}
+
+impl ModelGraph for TfGraph {
+    fn ops(&self, names: &mut NameDecoder) -> Result<Vec<Op>, InvalidNameError> {
+        let mut decoded = match decode_graph_def(self) {
+            Some(graph_def) => graph_def
+                .ops(names)?
+                .into_iter()
+                .map(|op| (op.name.clone(), op))
+                .collect(),
+            None => HashMap::new(),
+        };
+        self.operation_iter()
+            .enumerate()
+            .map(|(i, op)| {
+                let name = op_name(&op, || format!("operation {}", i))?;
+                let ty = op.op_type().map_err(|_| InvalidNameError {
+                    name: format!("type of {}", name),
+                })?;
+                let inputs = (0..op.num_inputs())
+                    .map(|i| {
+                        let (input, index) = op.input(i);
+                        Ok(Port {
+                            op: op_name(&input, || format!("input {} of {}", i, name))?,
+                            index,
+                            output_arg: None,
+                            dim: output_dims(self, &input, index),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, InvalidNameError>>()?;
+                let control_inputs = op
+                    .control_inputs()
+                    .iter()
+                    .map(|input| op_name(input, || format!("control input of {}", name)))
+                    .collect::<Result<Vec<_>, InvalidNameError>>()?;
+                let output_dims = (0..op.num_outputs())
+                    .map(|index| output_dims(self, &op, index))
+                    .collect();
+                let decoded = decoded.remove(&name);
+                Ok(Op {
+                    output_dims,
+                    parameters: decoded.as_ref().map_or(0, |op| op.parameters),
+                    attributes: decoded
+                        .as_ref()
+                        .map(|op| op.attributes.clone())
+                        .unwrap_or_default(),
+                    call: decoded.as_ref().and_then(|op| op.call.clone()),
+                    branches: decoded.map(|op| op.branches).unwrap_or_default(),
+                    name,
+                    ty,
+                    inputs,
+                    control_inputs,
+                })
+            })
+            .collect()
+    }
+
+    fn functions(&self, names: &mut NameDecoder) -> Result<Vec<Function>, InvalidNameError> {
+        match decode_graph_def(self) {
+            Some(graph_def) => graph_def.functions(names),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+/// Turns a failure to import a graph into an error, picking out ops which aren't registered so
+/// they can be named.
+fn import_error(input: &Path, status: Status) -> Error {
+    // The status message is "Op type not registered 'Name' in binary running on ..."
+    let message = status.to_string();
+    let unknown_op = message
+        .split("Op type not registered '")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').next());
+    match unknown_op {
+        Some(op) => Error::UnknownOp { op: op.to_string() },
+        None => Error::Parse {
+            path: input.to_path_buf(),
+            message: message.to_string(),
+        },
+    }
+}
+
+/// Loads a tensorflow graph from either a frozen GraphDef, in the binary or text format, or a
+/// SavedModel directory.
+pub fn load_graph(input: &Path, tags: &[String]) -> Result<TfGraph, Error> {
+    let mut graph = TfGraph::new();
+    if input.is_dir() {
+        SavedModelBundle::load(&SessionOptions::new(), tags, &mut graph, input)
+            .map_err(|e| import_error(input, e))?;
+    } else {
+        let mut bytes = fs::read(input).map_err(|source| Error::Io {
+            path: input.to_path_buf(),
+            source,
+        })?;
+        if pbtxt::is_text(&bytes) {
+            let parse_error = |message: String| Error::Parse {
+                path: input.to_path_buf(),
+                message,
+            };
+            let text = String::from_utf8(bytes).map_err(|e| parse_error(e.to_string()))?;
+            bytes = pbtxt::graph_def_to_binary(&text).map_err(|e| parse_error(e.to_string()))?;
+        }
+        graph
+            .import_graph_def(&bytes, &ImportGraphDefOptions::new())
+            .map_err(|e| import_error(input, e))?;
+    }
+    Ok(graph)
+}
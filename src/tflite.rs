@@ -0,0 +1,1015 @@
+//! Front end for TensorFlow Lite flatbuffer models.
+use crate::error::{Error, InvalidNameError};
+use crate::model::{ModelGraph, NameDecoder, Op, Port};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A verified TensorFlow Lite model.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Model {
+    bytes: Vec<u8>,
+}
+
+/// Why a TensorFlow Lite model couldn't be decoded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The flatbuffer doesn't match the schema
+    Flatbuffer(flatbuffers::InvalidFlatbuffer),
+    /// An operator uses a tensor which isn't in its subgraph
+    TensorIndex { subgraph: usize, index: i32 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Flatbuffer(e) => e.fmt(f),
+            DecodeError::TensorIndex { subgraph, index } => write!(
+                f,
+                "an operator in subgraph {} uses tensor {} which doesn't exist",
+                subgraph, index
+            ),
+        }
+    }
+}
+
+impl StdError for DecodeError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            DecodeError::Flatbuffer(e) => Some(e),
+            DecodeError::TensorIndex { .. } => None,
+        }
+    }
+}
+
+/// Checks every tensor used by an operator is in its subgraph, so the tensors can be indexed
+/// directly once the model is decoded.
+fn check_tensor_indices(model: &schema::Model) -> Result<(), DecodeError> {
+    for (s, subgraph) in model.subgraphs().into_iter().flatten().enumerate() {
+        let tensors = subgraph.tensors().map_or(0, |t| t.len());
+        for operator in subgraph.operators().into_iter().flatten() {
+            let inputs = operator.inputs().into_iter().flatten();
+            let outputs = operator.outputs().into_iter().flatten();
+            // Optional inputs which aren't given are -1
+            for index in inputs.chain(outputs) {
+                if index != -1 && !usize::try_from(index).is_ok_and(|t| t < tensors) {
+                    return Err(DecodeError::TensorIndex { subgraph: s, index });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Model {
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let model = flatbuffers::root::<schema::Model>(bytes).map_err(DecodeError::Flatbuffer)?;
+        check_tensor_indices(&model)?;
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    pub fn load(input: &Path) -> Result<Self, Error> {
+        let bytes = fs::read(input).map_err(|source| Error::Io {
+            path: input.to_path_buf(),
+            source,
+        })?;
+        Self::decode(&bytes).map_err(|e| Error::Parse {
+            path: input.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+}
+
+impl ModelGraph for Model {
+    fn ops(&self, names: &mut NameDecoder) -> Result<Vec<Op>, InvalidNameError> {
+        // The buffer was verified when the model was decoded
+        let model = unsafe { flatbuffers::root_unchecked::<schema::Model>(&self.bytes) };
+        model_ops(&model, names)
+    }
+
+    /// Each subgraph is drawn as a region when there's more than one.
+    fn regions(&self, names: &mut NameDecoder) -> Result<Vec<(String, String)>, InvalidNameError> {
+        let model = unsafe { flatbuffers::root_unchecked::<schema::Model>(&self.bytes) };
+        let subgraphs = match model.subgraphs() {
+            Some(subgraphs) if subgraphs.len() > 1 => subgraphs,
+            _ => return Ok(vec![]),
+        };
+        subgraphs
+            .iter()
+            .enumerate()
+            .map(|(s, subgraph)| {
+                let name = subgraph_name(s, &subgraph, names)?;
+                Ok((name.clone(), format!("subgraph {}", name)))
+            })
+            .collect()
+    }
+}
+
+/// Name of a builtin operator, these are the `BuiltinOperator` enum in the schema.
+fn builtin_name(code: i32) -> String {
+    const NAMES: &[&str] = &[
+        "ADD",
+        "AVERAGE_POOL_2D",
+        "CONCATENATION",
+        "CONV_2D",
+        "DEPTHWISE_CONV_2D",
+        "DEPTH_TO_SPACE",
+        "DEQUANTIZE",
+        "EMBEDDING_LOOKUP",
+        "FLOOR",
+        "FULLY_CONNECTED",
+        "HASHTABLE_LOOKUP",
+        "L2_NORMALIZATION",
+        "L2_POOL_2D",
+        "LOCAL_RESPONSE_NORMALIZATION",
+        "LOGISTIC",
+        "LSH_PROJECTION",
+        "LSTM",
+        "MAX_POOL_2D",
+        "MUL",
+        "RELU",
+        "RELU_N1_TO_1",
+        "RELU6",
+        "RESHAPE",
+        "RESIZE_BILINEAR",
+        "RNN",
+        "SOFTMAX",
+        "SPACE_TO_DEPTH",
+        "SVDF",
+        "TANH",
+        "CONCAT_EMBEDDINGS",
+        "SKIP_GRAM",
+        "CALL",
+        "CUSTOM",
+        "EMBEDDING_LOOKUP_SPARSE",
+        "PAD",
+        "UNIDIRECTIONAL_SEQUENCE_RNN",
+        "GATHER",
+        "BATCH_TO_SPACE_ND",
+        "SPACE_TO_BATCH_ND",
+        "TRANSPOSE",
+        "MEAN",
+        "SUB",
+        "DIV",
+        "SQUEEZE",
+        "UNIDIRECTIONAL_SEQUENCE_LSTM",
+        "STRIDED_SLICE",
+        "BIDIRECTIONAL_SEQUENCE_RNN",
+        "EXP",
+        "TOPK_V2",
+        "SPLIT",
+        "LOG_SOFTMAX",
+        "DELEGATE",
+        "BIDIRECTIONAL_SEQUENCE_LSTM",
+        "CAST",
+        "PRELU",
+        "MAXIMUM",
+        "ARG_MAX",
+        "MINIMUM",
+        "LESS",
+        "NEG",
+        "PADV2",
+        "GREATER",
+        "GREATER_EQUAL",
+        "LESS_EQUAL",
+        "SELECT",
+        "SLICE",
+        "SIN",
+        "TRANSPOSE_CONV",
+        "SPARSE_TO_DENSE",
+        "TILE",
+        "EXPAND_DIMS",
+        "EQUAL",
+        "NOT_EQUAL",
+        "LOG",
+        "SUM",
+        "SQRT",
+        "RSQRT",
+        "SHAPE",
+        "POW",
+        "ARG_MIN",
+        "FAKE_QUANT",
+        "REDUCE_PROD",
+        "REDUCE_MAX",
+        "PACK",
+        "LOGICAL_OR",
+        "ONE_HOT",
+        "LOGICAL_AND",
+        "LOGICAL_NOT",
+        "UNPACK",
+        "REDUCE_MIN",
+        "FLOOR_DIV",
+        "REDUCE_ANY",
+        "SQUARE",
+        "ZEROS_LIKE",
+        "FILL",
+        "FLOOR_MOD",
+        "RANGE",
+        "RESIZE_NEAREST_NEIGHBOR",
+        "LEAKY_RELU",
+        "SQUARED_DIFFERENCE",
+        "MIRROR_PAD",
+        "ABS",
+        "SPLIT_V",
+        "UNIQUE",
+        "CEIL",
+        "REVERSE_V2",
+        "ADD_N",
+        "GATHER_ND",
+        "COS",
+        "WHERE",
+        "RANK",
+        "ELU",
+        "REVERSE_SEQUENCE",
+        "MATRIX_DIAG",
+        "QUANTIZE",
+        "MATRIX_SET_DIAG",
+        "ROUND",
+        "HARD_SWISH",
+        "IF",
+        "WHILE",
+        "NON_MAX_SUPPRESSION_V4",
+        "NON_MAX_SUPPRESSION_V5",
+        "SCATTER_ND",
+        "SELECT_V2",
+        "DENSIFY",
+        "SEGMENT_SUM",
+        "BATCH_MATMUL",
+        "PLACEHOLDER_FOR_GREATER_OP_CODES",
+        "CUMSUM",
+        "CALL_ONCE",
+        "BROADCAST_TO",
+        "RFFT2D",
+        "CONV_3D",
+        "IMAG",
+        "REAL",
+        "COMPLEX_ABS",
+        "HASHTABLE",
+        "HASHTABLE_FIND",
+        "HASHTABLE_IMPORT",
+        "HASHTABLE_SIZE",
+        "REDUCE_ALL",
+        "CONV_3D_TRANSPOSE",
+        "VAR_HANDLE",
+        "READ_VARIABLE",
+        "ASSIGN_VARIABLE",
+        "BROADCAST_ARGS",
+        "RANDOM_STANDARD_NORMAL",
+        "BUCKETIZE",
+        "RANDOM_UNIFORM",
+        "MULTINOMIAL",
+        "GELU",
+        "DYNAMIC_UPDATE_SLICE",
+        "RELU_0_TO_1",
+        "UNSORTED_SEGMENT_PROD",
+        "UNSORTED_SEGMENT_MAX",
+        "UNSORTED_SEGMENT_SUM",
+        "ATAN2",
+        "UNSORTED_SEGMENT_MIN",
+        "SIGN",
+    ];
+    usize::try_from(code)
+        .ok()
+        .and_then(|code| NAMES.get(code))
+        .map_or_else(|| format!("BUILTIN_{}", code), |name| name.to_string())
+}
+
+/// Name of a `TensorType` from the schema.
+fn tensor_type_name(ty: i8) -> String {
+    const NAMES: &[&str] = &[
+        "float32",
+        "float16",
+        "int32",
+        "uint8",
+        "int64",
+        "string",
+        "bool",
+        "int16",
+        "complex64",
+        "int8",
+        "float64",
+        "complex128",
+        "uint64",
+        "resource",
+        "variant",
+        "uint32",
+        "uint16",
+        "int4",
+    ];
+    usize::try_from(ty)
+        .ok()
+        .and_then(|ty| NAMES.get(ty))
+        .map_or_else(|| format!("type {}", ty), |name| name.to_string())
+}
+
+fn op_type(
+    code: &schema::OperatorCode,
+    names: &mut NameDecoder,
+) -> Result<String, InvalidNameError> {
+    // Newer models store the code in `builtin_code`, older ones only have the deprecated field
+    // which is limited to 127
+    let builtin = code
+        .builtin_code()
+        .max(code.deprecated_builtin_code() as i32);
+    match code.custom_code() {
+        Some(custom) if builtin == 32 => names.decode(custom),
+        _ => Ok(builtin_name(builtin)),
+    }
+}
+
+/// Shape of a tensor, `None` if the file doesn't have one.
+fn tensor_dims(tensor: &schema::Tensor) -> Option<Vec<Option<usize>>> {
+    // The signature has -1 for dynamic dimensions while the shape has 1 in their place
+    tensor
+        .shape_signature()
+        .filter(|s| !s.is_empty())
+        .or_else(|| tensor.shape())
+        .map(|shape| shape.iter().map(|d| usize::try_from(d).ok()).collect())
+}
+
+/// Describes the quantisation of a tensor, per-channel quantisation is summarised rather than
+/// listing every scale.
+fn quantisation(tensor: &schema::Tensor) -> Option<String> {
+    let params = tensor.quantization()?;
+    let scale = params.scale().filter(|s| !s.is_empty())?;
+    let zero_point = params.zero_point();
+    if scale.len() == 1 {
+        let zero_point = zero_point.filter(|z| !z.is_empty()).map_or(0, |z| z.get(0));
+        Some(format!("scale {}, zero point {}", scale.get(0), zero_point))
+    } else {
+        Some(format!(
+            "per-channel, {} scales on dimension {}",
+            scale.len(),
+            params.quantized_dimension()
+        ))
+    }
+}
+
+/// Adds the type and quantisation of an output tensor to an op's attributes.
+fn tensor_attributes(
+    tensor: &schema::Tensor,
+    index: usize,
+    attributes: &mut BTreeMap<String, String>,
+) {
+    attributes.insert(format!("dtype:{}", index), tensor_type_name(tensor.type_()));
+    if let Some(quantisation) = quantisation(tensor) {
+        attributes.insert(format!("quantisation:{}", index), quantisation);
+    }
+}
+
+/// Name of a subgraph, falling back to its index for unnamed ones.
+fn subgraph_name(
+    index: usize,
+    subgraph: &schema::SubGraph,
+    names: &mut NameDecoder,
+) -> Result<String, InvalidNameError> {
+    match subgraph.name().filter(|n| !n.is_empty()) {
+        Some(name) => names.decode(name),
+        None => Ok(format!("subgraph_{}", index)),
+    }
+}
+
+fn model_ops(model: &schema::Model, names: &mut NameDecoder) -> Result<Vec<Op>, InvalidNameError> {
+    let codes = model
+        .operator_codes()
+        .map(|codes| codes.iter().map(|c| op_type(&c, names)).collect())
+        .transpose()?
+        .unwrap_or_else(Vec::new);
+    let buffers = model.buffers();
+    let subgraphs = match model.subgraphs() {
+        Some(subgraphs) => subgraphs,
+        None => return Ok(vec![]),
+    };
+
+    let mut ops = vec![];
+    for (s, subgraph) in subgraphs.iter().enumerate() {
+        // When there's more than one subgraph each one is put in its own scope, which is drawn
+        // as a region
+        let scope = if subgraphs.len() > 1 {
+            format!("{}/", subgraph_name(s, &subgraph, names)?)
+        } else {
+            String::new()
+        };
+        let tensors = subgraph
+            .tensors()
+            .map(|t| t.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let tensor_names = tensors
+            .iter()
+            .map(|t| names.decode(t.name().unwrap_or_default()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tensor_name = |t: usize| format!("{}{}", scope, tensor_names[t]);
+
+        // Map every tensor to the op producing it and which output it is
+        let mut producers = HashMap::new();
+        let operators = subgraph
+            .operators()
+            .map(|o| o.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        // Tensor names don't have to be unique or even set, so ops fall back to their position
+        // when their name is empty or taken
+        let mut used = HashSet::new();
+        let mut unique_name = |t: Option<usize>, fallback: String| {
+            let name = t
+                .filter(|t| *t < tensors.len() && !tensor_names[*t].is_empty())
+                .map(tensor_name)
+                .filter(|name| !used.contains(name))
+                .unwrap_or(fallback);
+            used.insert(name.clone());
+            name
+        };
+        let mut op_names = vec![];
+        for (i, operator) in operators.iter().enumerate() {
+            let outputs = operator.outputs().map(|o| o.iter().collect::<Vec<_>>());
+            let outputs = outputs.unwrap_or_default();
+            // Operators don't have names so use the name of their first output
+            let first = outputs.first().and_then(|o| usize::try_from(*o).ok());
+            let name = unique_name(first, format!("{}operator_{}", scope, i));
+            for (j, output) in outputs.iter().enumerate() {
+                if let Ok(t) = usize::try_from(*output) {
+                    producers.insert(t, (name.clone(), j));
+                }
+            }
+            op_names.push(name);
+        }
+
+        let graph_inputs = subgraph
+            .inputs()
+            .map(|i| i.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        for (t, tensor) in tensors.iter().enumerate() {
+            if producers.contains_key(&t) {
+                continue;
+            }
+            let has_data = buffers
+                .filter(|b| (tensor.buffer() as usize) < b.len())
+                .and_then(|b| b.get(tensor.buffer() as usize).data())
+                .is_some_and(|d| !d.is_empty());
+            let ty = if graph_inputs.contains(&(t as i32)) {
+                "Input"
+            } else if has_data {
+                "Const"
+            } else if tensor.is_variable() {
+                "Variable"
+            } else {
+                continue;
+            };
+            let parameters = if ty == "Input" {
+                0
+            } else {
+                tensor_dims(tensor)
+                    .and_then(|dims| dims.into_iter().product::<Option<usize>>())
+                    .unwrap_or(0)
+            };
+            let mut attributes = BTreeMap::new();
+            tensor_attributes(tensor, 0, &mut attributes);
+            let name = unique_name(Some(t), format!("{}tensor_{}", scope, t));
+            producers.insert(t, (name.clone(), 0));
+            ops.push(Op {
+                name,
+                ty: ty.to_string(),
+                inputs: vec![],
+                control_inputs: vec![],
+                output_dims: vec![tensor_dims(tensor)],
+                parameters,
+                attributes,
+                call: None,
+                branches: vec![],
+            });
+        }
+
+        for (operator, name) in operators.iter().zip(op_names) {
+            let ty = codes
+                .get(operator.opcode_index() as usize)
+                .cloned()
+                .unwrap_or_else(|| "UNKNOWN".to_string());
+            // Optional inputs which aren't given are -1, the others were checked to be in range
+            // when the model was decoded
+            let inputs = operator
+                .inputs()
+                .map(|i| i.iter().collect::<Vec<_>>())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|t| usize::try_from(t).ok())
+                .filter_map(|t| {
+                    let (op, index) = producers.get(&t)?;
+                    Some(Port {
+                        op: op.clone(),
+                        index: *index,
+                        output_arg: None,
+                        dim: tensor_dims(&tensors[t]),
+                    })
+                })
+                .collect();
+            let mut attributes = BTreeMap::new();
+            let outputs = operator
+                .outputs()
+                .map(|o| o.iter().collect::<Vec<_>>())
+                .unwrap_or_default();
+            let mut output_dims = vec![];
+            for (j, output) in outputs.into_iter().enumerate() {
+                let tensor = usize::try_from(output).ok().and_then(|t| tensors.get(t));
+                if let Some(tensor) = tensor {
+                    tensor_attributes(tensor, j, &mut attributes);
+                }
+                output_dims.push(tensor.and_then(tensor_dims));
+            }
+            ops.push(Op {
+                name,
+                ty,
+                inputs,
+                control_inputs: vec![],
+                output_dims,
+                parameters: 0,
+                attributes,
+                call: None,
+                branches: vec![],
+            });
+        }
+    }
+    Ok(ops)
+}
+
+/// The subset of the TensorFlow Lite flatbuffer schema (schema.fbs) needed to build the graph.
+/// Field offsets are `4 + 2 * field id`, fields which aren't read are skipped over by the
+/// verifier. Strings are read as bytes so invalid UTF-8 can be escaped rather than failing the
+/// verifier.
+mod schema {
+    use flatbuffers::{
+        Follow, ForwardsUOffset, InvalidFlatbuffer, Table, Vector, Verifiable, Verifier,
+    };
+
+    type Tables<'a, T> = ForwardsUOffset<Vector<'a, ForwardsUOffset<T>>>;
+    type Scalars<'a, T> = ForwardsUOffset<Vector<'a, T>>;
+
+    macro_rules! table {
+        ($name:ident) => {
+            #[derive(Clone, Copy)]
+            pub struct $name<'a>(Table<'a>);
+
+            impl<'a> Follow<'a> for $name<'a> {
+                type Inner = Self;
+
+                unsafe fn follow(buf: &'a [u8], loc: usize) -> Self {
+                    $name(Table::new(buf, loc))
+                }
+            }
+        };
+    }
+
+    // All the accessors are only called on tables which have been through the verifier, so the
+    // field types match what's been checked and the unsafe reads are sound
+
+    table!(Model);
+
+    impl<'a> Model<'a> {
+        const OPERATOR_CODES: u16 = 6;
+        const SUBGRAPHS: u16 = 8;
+        const BUFFERS: u16 = 12;
+
+        pub fn operator_codes(&self) -> Option<Vector<'a, ForwardsUOffset<OperatorCode<'a>>>> {
+            unsafe {
+                self.0
+                    .get::<Tables<OperatorCode>>(Self::OPERATOR_CODES, None)
+            }
+        }
+
+        pub fn subgraphs(&self) -> Option<Vector<'a, ForwardsUOffset<SubGraph<'a>>>> {
+            unsafe { self.0.get::<Tables<SubGraph>>(Self::SUBGRAPHS, None) }
+        }
+
+        pub fn buffers(&self) -> Option<Vector<'a, ForwardsUOffset<Buffer<'a>>>> {
+            unsafe { self.0.get::<Tables<Buffer>>(Self::BUFFERS, None) }
+        }
+    }
+
+    impl Verifiable for Model<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Tables<OperatorCode>>("operator_codes", Self::OPERATOR_CODES, false)?
+                .visit_field::<Tables<SubGraph>>("subgraphs", Self::SUBGRAPHS, false)?
+                .visit_field::<Tables<Buffer>>("buffers", Self::BUFFERS, false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    table!(OperatorCode);
+
+    impl<'a> OperatorCode<'a> {
+        const DEPRECATED_BUILTIN_CODE: u16 = 4;
+        const CUSTOM_CODE: u16 = 6;
+        const BUILTIN_CODE: u16 = 10;
+
+        pub fn deprecated_builtin_code(&self) -> i8 {
+            unsafe { self.0.get::<i8>(Self::DEPRECATED_BUILTIN_CODE, Some(0)) }.unwrap_or(0)
+        }
+
+        pub fn custom_code(&self) -> Option<&'a [u8]> {
+            unsafe { self.0.get::<Scalars<u8>>(Self::CUSTOM_CODE, None) }.map(|s| s.bytes())
+        }
+
+        pub fn builtin_code(&self) -> i32 {
+            unsafe { self.0.get::<i32>(Self::BUILTIN_CODE, Some(0)) }.unwrap_or(0)
+        }
+    }
+
+    impl Verifiable for OperatorCode<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<i8>(
+                    "deprecated_builtin_code",
+                    Self::DEPRECATED_BUILTIN_CODE,
+                    false,
+                )?
+                .visit_field::<Scalars<u8>>("custom_code", Self::CUSTOM_CODE, false)?
+                .visit_field::<i32>("builtin_code", Self::BUILTIN_CODE, false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    table!(SubGraph);
+
+    impl<'a> SubGraph<'a> {
+        const TENSORS: u16 = 4;
+        const INPUTS: u16 = 6;
+        const OPERATORS: u16 = 10;
+        const NAME: u16 = 12;
+
+        pub fn tensors(&self) -> Option<Vector<'a, ForwardsUOffset<Tensor<'a>>>> {
+            unsafe { self.0.get::<Tables<Tensor>>(Self::TENSORS, None) }
+        }
+
+        pub fn inputs(&self) -> Option<Vector<'a, i32>> {
+            unsafe { self.0.get::<Scalars<i32>>(Self::INPUTS, None) }
+        }
+
+        pub fn operators(&self) -> Option<Vector<'a, ForwardsUOffset<Operator<'a>>>> {
+            unsafe { self.0.get::<Tables<Operator>>(Self::OPERATORS, None) }
+        }
+
+        pub fn name(&self) -> Option<&'a [u8]> {
+            unsafe { self.0.get::<Scalars<u8>>(Self::NAME, None) }.map(|s| s.bytes())
+        }
+    }
+
+    impl Verifiable for SubGraph<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Tables<Tensor>>("tensors", Self::TENSORS, false)?
+                .visit_field::<Scalars<i32>>("inputs", Self::INPUTS, false)?
+                .visit_field::<Tables<Operator>>("operators", Self::OPERATORS, false)?
+                .visit_field::<Scalars<u8>>("name", Self::NAME, false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    table!(Tensor);
+
+    impl<'a> Tensor<'a> {
+        const SHAPE: u16 = 4;
+        const TYPE: u16 = 6;
+        const BUFFER: u16 = 8;
+        const NAME: u16 = 10;
+        const QUANTIZATION: u16 = 12;
+        const IS_VARIABLE: u16 = 14;
+        const SHAPE_SIGNATURE: u16 = 18;
+
+        pub fn shape(&self) -> Option<Vector<'a, i32>> {
+            unsafe { self.0.get::<Scalars<i32>>(Self::SHAPE, None) }
+        }
+
+        pub fn type_(&self) -> i8 {
+            unsafe { self.0.get::<i8>(Self::TYPE, Some(0)) }.unwrap_or(0)
+        }
+
+        pub fn buffer(&self) -> u32 {
+            unsafe { self.0.get::<u32>(Self::BUFFER, Some(0)) }.unwrap_or(0)
+        }
+
+        pub fn name(&self) -> Option<&'a [u8]> {
+            unsafe { self.0.get::<Scalars<u8>>(Self::NAME, None) }.map(|s| s.bytes())
+        }
+
+        pub fn quantization(&self) -> Option<QuantizationParameters<'a>> {
+            unsafe {
+                self.0
+                    .get::<ForwardsUOffset<QuantizationParameters>>(Self::QUANTIZATION, None)
+            }
+        }
+
+        pub fn is_variable(&self) -> bool {
+            unsafe { self.0.get::<bool>(Self::IS_VARIABLE, Some(false)) }.unwrap_or(false)
+        }
+
+        pub fn shape_signature(&self) -> Option<Vector<'a, i32>> {
+            unsafe { self.0.get::<Scalars<i32>>(Self::SHAPE_SIGNATURE, None) }
+        }
+    }
+
+    impl Verifiable for Tensor<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Scalars<i32>>("shape", Self::SHAPE, false)?
+                .visit_field::<i8>("type", Self::TYPE, false)?
+                .visit_field::<u32>("buffer", Self::BUFFER, false)?
+                .visit_field::<Scalars<u8>>("name", Self::NAME, false)?
+                .visit_field::<ForwardsUOffset<QuantizationParameters>>(
+                    "quantization",
+                    Self::QUANTIZATION,
+                    false,
+                )?
+                .visit_field::<bool>("is_variable", Self::IS_VARIABLE, false)?
+                .visit_field::<Scalars<i32>>("shape_signature", Self::SHAPE_SIGNATURE, false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    table!(QuantizationParameters);
+
+    impl<'a> QuantizationParameters<'a> {
+        const SCALE: u16 = 8;
+        const ZERO_POINT: u16 = 10;
+        const QUANTIZED_DIMENSION: u16 = 16;
+
+        pub fn scale(&self) -> Option<Vector<'a, f32>> {
+            unsafe { self.0.get::<Scalars<f32>>(Self::SCALE, None) }
+        }
+
+        pub fn zero_point(&self) -> Option<Vector<'a, i64>> {
+            unsafe { self.0.get::<Scalars<i64>>(Self::ZERO_POINT, None) }
+        }
+
+        pub fn quantized_dimension(&self) -> i32 {
+            unsafe { self.0.get::<i32>(Self::QUANTIZED_DIMENSION, Some(0)) }.unwrap_or(0)
+        }
+    }
+
+    impl Verifiable for QuantizationParameters<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Scalars<f32>>("scale", Self::SCALE, false)?
+                .visit_field::<Scalars<i64>>("zero_point", Self::ZERO_POINT, false)?
+                .visit_field::<i32>("quantized_dimension", Self::QUANTIZED_DIMENSION, false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    table!(Operator);
+
+    impl<'a> Operator<'a> {
+        const OPCODE_INDEX: u16 = 4;
+        const INPUTS: u16 = 6;
+        const OUTPUTS: u16 = 8;
+
+        pub fn opcode_index(&self) -> u32 {
+            unsafe { self.0.get::<u32>(Self::OPCODE_INDEX, Some(0)) }.unwrap_or(0)
+        }
+
+        pub fn inputs(&self) -> Option<Vector<'a, i32>> {
+            unsafe { self.0.get::<Scalars<i32>>(Self::INPUTS, None) }
+        }
+
+        pub fn outputs(&self) -> Option<Vector<'a, i32>> {
+            unsafe { self.0.get::<Scalars<i32>>(Self::OUTPUTS, None) }
+        }
+    }
+
+    impl Verifiable for Operator<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<u32>("opcode_index", Self::OPCODE_INDEX, false)?
+                .visit_field::<Scalars<i32>>("inputs", Self::INPUTS, false)?
+                .visit_field::<Scalars<i32>>("outputs", Self::OUTPUTS, false)?
+                .finish();
+            Ok(())
+        }
+    }
+
+    table!(Buffer);
+
+    impl<'a> Buffer<'a> {
+        const DATA: u16 = 4;
+
+        pub fn data(&self) -> Option<Vector<'a, u8>> {
+            unsafe { self.0.get::<Scalars<u8>>(Self::DATA, None) }
+        }
+    }
+
+    impl Verifiable for Buffer<'_> {
+        fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+            v.visit_table(pos)?
+                .visit_field::<Scalars<u8>>("data", Self::DATA, false)?
+                .finish();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
+
+    type Offset = WIPOffset<UnionWIPOffset>;
+
+    fn tensor(b: &mut FlatBufferBuilder, name: &str, shape: &[i32], buffer: u32) -> Offset {
+        let name = b.create_string(name);
+        let shape = b.create_vector(shape);
+        let start = b.start_table();
+        b.push_slot_always(4, shape);
+        b.push_slot::<u32>(8, buffer, 0);
+        b.push_slot_always(10, name);
+        b.end_table(start).as_union_value()
+    }
+
+    fn operator(b: &mut FlatBufferBuilder, code: u32, inputs: &[i32], outputs: &[i32]) -> Offset {
+        let inputs = b.create_vector(inputs);
+        let outputs = b.create_vector(outputs);
+        let start = b.start_table();
+        b.push_slot::<u32>(4, code, 0);
+        b.push_slot_always(6, inputs);
+        b.push_slot_always(8, outputs);
+        b.end_table(start).as_union_value()
+    }
+
+    fn subgraph(
+        b: &mut FlatBufferBuilder,
+        name: &str,
+        tensors: &[Offset],
+        inputs: &[i32],
+        operators: &[Offset],
+    ) -> Offset {
+        let tensors = b.create_vector(tensors);
+        let inputs = b.create_vector(inputs);
+        let operators = b.create_vector(operators);
+        let name = b.create_string(name);
+        let start = b.start_table();
+        b.push_slot_always(4, tensors);
+        b.push_slot_always(6, inputs);
+        b.push_slot_always(10, operators);
+        b.push_slot_always(12, name);
+        b.end_table(start).as_union_value()
+    }
+
+    /// Finishes a model with the subgraphs, the operator codes are ADD and FULLY_CONNECTED and
+    /// buffer 1 has data.
+    fn model(mut b: FlatBufferBuilder, subgraphs: &[Offset]) -> Vec<u8> {
+        let subgraphs = b.create_vector(subgraphs);
+        let codes = [0, 9]
+            .iter()
+            .map(|code| {
+                let start = b.start_table();
+                b.push_slot::<i32>(10, *code, 0);
+                b.end_table(start).as_union_value()
+            })
+            .collect::<Vec<_>>();
+        let codes = b.create_vector(&codes);
+        let empty = {
+            let start = b.start_table();
+            b.end_table(start).as_union_value()
+        };
+        let data = b.create_vector(&[0u8; 8]);
+        let full = {
+            let start = b.start_table();
+            b.push_slot_always(4, data);
+            b.end_table(start).as_union_value()
+        };
+        let buffers = b.create_vector(&[empty, full]);
+        let start = b.start_table();
+        b.push_slot_always(6, codes);
+        b.push_slot_always(8, subgraphs);
+        b.push_slot_always(12, buffers);
+        let root = b.end_table(start);
+        b.finish(root, Some("TFL3"));
+        b.finished_data().to_vec()
+    }
+
+    /// An input and a constant feeding a fully connected layer.
+    fn dense(b: &mut FlatBufferBuilder, name: &str) -> Offset {
+        let tensors = [
+            tensor(b, "input", &[1, 4], 0),
+            tensor(b, "weights", &[2, 4], 1),
+            tensor(b, "dense", &[1, 2], 0),
+        ];
+        let operators = [operator(b, 1, &[0, 1, -1], &[2])];
+        subgraph(b, name, &tensors, &[0], &operators)
+    }
+
+    #[test]
+    fn reads_operators_and_tensors() {
+        let mut b = FlatBufferBuilder::new();
+        let main = dense(&mut b, "main");
+        let model = Model::decode(&model(b, &[main])).unwrap();
+        let ops = model.ops(&mut NameDecoder::new(false)).unwrap();
+
+        let summary = ops
+            .iter()
+            .map(|op| (op.name.as_str(), op.ty.as_str(), op.parameters))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("input", "Input", 0),
+                ("weights", "Const", 8),
+                ("dense", "FULLY_CONNECTED", 0)
+            ]
+        );
+        let inputs = ops[2]
+            .inputs
+            .iter()
+            .map(|port| (port.op.as_str(), port.dim.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            vec![
+                ("input", Some(vec![Some(1), Some(4)])),
+                ("weights", Some(vec![Some(2), Some(4)]))
+            ]
+        );
+        assert!(model
+            .regions(&mut NameDecoder::new(false))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn scopes_subgraphs_as_regions() {
+        let mut b = FlatBufferBuilder::new();
+        let main = dense(&mut b, "main");
+        let other = dense(&mut b, "");
+        let model = Model::decode(&model(b, &[main, other])).unwrap();
+        let mut names = NameDecoder::new(false);
+
+        let ops = model.ops(&mut names).unwrap();
+        assert_eq!(ops[2].name, "main/dense");
+        assert_eq!(ops[5].name, "subgraph_1/dense");
+        assert_eq!(
+            model.regions(&mut names).unwrap(),
+            vec![
+                ("main".to_string(), "subgraph main".to_string()),
+                ("subgraph_1".to_string(), "subgraph subgraph_1".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn tensor_out_of_range_is_an_error() {
+        let mut b = FlatBufferBuilder::new();
+        let tensors = [tensor(&mut b, "input", &[1], 0)];
+        let operators = [operator(&mut b, 0, &[0, 3], &[0])];
+        let main = subgraph(&mut b, "main", &tensors, &[0], &operators);
+
+        assert_eq!(
+            Model::decode(&model(b, &[main])),
+            Err(DecodeError::TensorIndex {
+                subgraph: 0,
+                index: 3
+            })
+        );
+    }
+
+    #[test]
+    fn names_operators_uniquely() {
+        let mut b = FlatBufferBuilder::new();
+        let tensors = [
+            tensor(&mut b, "input", &[1], 0),
+            tensor(&mut b, "x", &[1], 0),
+            tensor(&mut b, "x", &[1], 0),
+            tensor(&mut b, "", &[1], 0),
+            tensor(&mut b, "x", &[1], 1),
+        ];
+        let operators = [
+            operator(&mut b, 0, &[0, 4], &[1]),
+            operator(&mut b, 0, &[1], &[2]),
+            operator(&mut b, 0, &[2], &[3]),
+        ];
+        let main = subgraph(&mut b, "main", &tensors, &[0], &operators);
+        let model = Model::decode(&model(b, &[main])).unwrap();
+        let ops = model.ops(&mut NameDecoder::new(false)).unwrap();
+
+        let names = ops
+            .iter()
+            .map(|op| (op.name.as_str(), op.inputs.first().map(|p| p.op.as_str())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("input", None),
+                ("tensor_4", None),
+                ("x", Some("input")),
+                ("operator_1", Some("x")),
+                ("operator_2", Some("operator_1")),
+            ]
+        );
+    }
+}
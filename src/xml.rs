@@ -0,0 +1,310 @@
+//! Exports graphs as GraphML and GEXF for analysis in tools like Gephi, yEd or NetworkX.
+//!
+//! Nodes have their `name`, `type`, name `scope` (joined with `/`), control flow `region`
+//! (joined with `;`), `parameters`, the `output_shapes` of each output (joined with spaces) and
+//! every op attribute as `attr.<name>`. Collapsed blocks also have the number of `ops` inside them,
+//! their `op_types` as `type=count` joined with `;` and the shapes of their `block_inputs` and
+//! `block_outputs`.
+//! Edges have their `kind`, `input_index`, `output_index` and the `dim` of the tensor formatted
+//! like `[1, ?, 3]`, or `?` if the rank is unknown. Values which aren't known are left out.
+use crate::dot::name_scope;
+use crate::graph::{format_shape, Edge, EdgeKind, Node};
+use petgraph::graph::Graph;
+use petgraph::visit::EdgeRef;
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+
+#[derive(Clone, Copy)]
+enum Type {
+    String,
+    Int,
+    Long,
+}
+
+/// An attribute column, every node or edge has a value for some of them.
+struct Attribute {
+    name: String,
+    ty: Type,
+}
+
+impl Attribute {
+    fn new(name: impl Into<String>, ty: Type) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+fn node_attributes(graph: &Graph<Node, Edge>) -> Vec<Attribute> {
+    let mut attributes = vec![
+        Attribute::new("name", Type::String),
+        Attribute::new("type", Type::String),
+        Attribute::new("scope", Type::String),
+        Attribute::new("region", Type::String),
+        Attribute::new("ops", Type::Int),
+        Attribute::new("parameters", Type::Long),
+        Attribute::new("output_shapes", Type::String),
+        Attribute::new("op_types", Type::String),
+        Attribute::new("block_inputs", Type::String),
+        Attribute::new("block_outputs", Type::String),
+    ];
+    let op_attributes = graph
+        .node_weights()
+        .flat_map(|node| node.attributes().keys())
+        .collect::<BTreeSet<_>>();
+    attributes.extend(
+        op_attributes
+            .into_iter()
+            .map(|name| Attribute::new(format!("attr.{}", name), Type::String)),
+    );
+    attributes
+}
+
+fn shapes(dims: &[Option<Vec<Option<usize>>>]) -> String {
+    dims.iter()
+        .map(|dims| format_shape(dims.as_deref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Values of the node attributes, as the index of the attribute and the value.
+fn node_values(node: &Node, attributes: &[Attribute]) -> Vec<(usize, String)> {
+    let mut values = vec![
+        (0, node.name().to_string_lossy().into_owned()),
+        (1, node.ty().to_string()),
+        (2, name_scope(node).join("/")),
+    ];
+    if !node.region().is_empty() {
+        values.push((3, node.region().join(";")));
+    }
+    if let Some(summary) = node.summary() {
+        values.push((4, summary.ops().to_string()));
+    }
+    values.push((5, node.parameters().to_string()));
+    if !node.output_dims().is_empty() {
+        values.push((6, shapes(node.output_dims())));
+    }
+    if let Some(summary) = node.summary() {
+        let op_types = summary
+            .op_types()
+            .iter()
+            .map(|(ty, count)| format!("{}={}", ty, count))
+            .collect::<Vec<_>>();
+        values.push((7, op_types.join(";")));
+        if !summary.inputs().is_empty() {
+            values.push((8, shapes(summary.inputs())));
+        }
+        if !summary.outputs().is_empty() {
+            values.push((9, shapes(summary.outputs())));
+        }
+    }
+    for (i, attribute) in attributes.iter().enumerate().skip(10) {
+        if let Some(value) = node.attributes().get(&attribute.name["attr.".len()..]) {
+            values.push((i, value.clone()));
+        }
+    }
+    values
+}
+
+fn edge_attributes() -> Vec<Attribute> {
+    vec![
+        Attribute::new("kind", Type::String),
+        Attribute::new("input_index", Type::Int),
+        Attribute::new("output_index", Type::Int),
+        Attribute::new("dim", Type::String),
+    ]
+}
+
+fn edge_values(edge: &Edge) -> Vec<(usize, String)> {
+    let kind = match edge.kind() {
+        EdgeKind::Data => "data",
+        EdgeKind::Control => "control",
+        EdgeKind::Back => "back",
+        EdgeKind::Call => "call",
+    };
+    let mut values = vec![(0, kind.to_string())];
+    if let Some(index) = edge.input_index() {
+        values.push((1, index.to_string()));
+    }
+    if let Some(index) = edge.output_index() {
+        values.push((2, index.to_string()));
+    }
+    if matches!(edge.kind(), EdgeKind::Data | EdgeKind::Back) {
+        values.push((3, format_shape(edge.dim())));
+    }
+    values
+}
+
+/// Escapes text for XML attributes and element content, newlines are escaped so they aren't
+/// turned into spaces in attributes. Characters XML doesn't allow, even escaped, are replaced with
+/// U+FFFD.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_graphml(graph: &Graph<Node, Edge>, out: &mut String) -> fmt::Result {
+    let type_name = |ty| match ty {
+        Type::String => "string",
+        Type::Int => "int",
+        Type::Long => "long",
+    };
+    let nodes = node_attributes(graph);
+    let edges = edge_attributes();
+    out.push_str(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    );
+    for (kind, prefix, attributes) in [("node", "n", &nodes), ("edge", "e", &edges)] {
+        for (i, attribute) in attributes.iter().enumerate() {
+            writeln!(
+                out,
+                "  <key id=\"{}{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                prefix,
+                i,
+                kind,
+                escape(&attribute.name),
+                type_name(attribute.ty)
+            )?;
+        }
+    }
+    out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+    let data = |prefix, values: Vec<(usize, String)>| {
+        values
+            .into_iter()
+            .map(|(i, value)| {
+                format!(
+                    "      <data key=\"{}{}\">{}</data>\n",
+                    prefix,
+                    i,
+                    escape(&value)
+                )
+            })
+            .collect::<String>()
+    };
+    for idx in graph.node_indices() {
+        writeln!(
+            out,
+            "    <node id=\"n{}\">\n{}    </node>",
+            idx.index(),
+            data("n", node_values(&graph[idx], &nodes))
+        )?;
+    }
+    for edge in graph.edge_references() {
+        writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">\n{}    </edge>",
+            edge.id().index(),
+            edge.source().index(),
+            edge.target().index(),
+            data("e", edge_values(edge.weight()))
+        )?;
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    Ok(())
+}
+
+/// Renders the graph as GraphML.
+pub fn render_graphml(graph: &Graph<Node, Edge>) -> String {
+    let mut out = String::new();
+    write_graphml(graph, &mut out).expect("writing to a string can't fail");
+    out
+}
+
+fn write_gexf(graph: &Graph<Node, Edge>, out: &mut String) -> fmt::Result {
+    let type_name = |ty| match ty {
+        Type::String => "string",
+        Type::Int => "integer",
+        Type::Long => "long",
+    };
+    let nodes = node_attributes(graph);
+    let edges = edge_attributes();
+    out.push_str(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n  \
+         <graph defaultedgetype=\"directed\">\n",
+    );
+    for (class, attributes) in [("node", &nodes), ("edge", &edges)] {
+        writeln!(out, "    <attributes class=\"{}\">", class)?;
+        for (i, attribute) in attributes.iter().enumerate() {
+            writeln!(
+                out,
+                "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+                i,
+                escape(&attribute.name),
+                type_name(attribute.ty)
+            )?;
+        }
+        out.push_str("    </attributes>\n");
+    }
+    let attvalues = |values: Vec<(usize, String)>| {
+        let values = values
+            .into_iter()
+            .map(|(i, value)| {
+                format!(
+                    "          <attvalue for=\"{}\" value=\"{}\"/>\n",
+                    i,
+                    escape(&value)
+                )
+            })
+            .collect::<String>();
+        format!("        <attvalues>\n{}        </attvalues>\n", values)
+    };
+    out.push_str("    <nodes>\n");
+    for idx in graph.node_indices() {
+        let node = &graph[idx];
+        writeln!(
+            out,
+            "      <node id=\"{}\" label=\"{}\">\n{}      </node>",
+            idx.index(),
+            escape(&node.name().to_string_lossy()),
+            attvalues(node_values(node, &nodes))
+        )?;
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for edge in graph.edge_references() {
+        writeln!(
+            out,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\">\n{}      </edge>",
+            edge.id().index(),
+            edge.source().index(),
+            edge.target().index(),
+            attvalues(edge_values(edge.weight()))
+        )?;
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    Ok(())
+}
+
+/// Renders the graph as GEXF, the format Gephi uses.
+pub fn render_gexf(graph: &Graph<Node, Edge>) -> String {
+    let mut out = String::new();
+    write_gexf(graph, &mut out).expect("writing to a string can't fail");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup_and_control_characters() {
+        assert_eq!(
+            escape("a<b> & \"c\"\n\td\u{0}\u{1b}"),
+            "a&lt;b&gt; &amp; &quot;c&quot;&#10;\td\u{fffd}\u{fffd}"
+        );
+    }
+}